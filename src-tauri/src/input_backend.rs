@@ -0,0 +1,347 @@
+// Выбор бэкенда для эмуляции ввода (перемещение курсора, клики, клавиши).
+// xdotool работает только под X11 и на Wayland-композиторах тихо ничего не
+// делает (getmouselocation не возвращает данные, mousemove/click — no-op).
+// Поэтому бэкенд выбирается в рантайме по типу сессии, как это делает
+// rustdesk: xdotool под X11, ydotool/uinput под Wayland, enigo как последний
+// резерв, если ни один CLI-инструмент не найден.
+
+use std::env;
+use std::process::Command;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputBackend {
+    Xdotool,
+    Ydotool,
+    Enigo,
+}
+
+impl InputBackend {
+    pub fn label(&self) -> &'static str {
+        match self {
+            InputBackend::Xdotool => "xdotool",
+            InputBackend::Ydotool => "ydotool",
+            InputBackend::Enigo => "enigo",
+        }
+    }
+}
+
+pub fn is_wayland_session() -> bool {
+    env::var("XDG_SESSION_TYPE").map(|v| v.eq_ignore_ascii_case("wayland")).unwrap_or(false)
+        || env::var("WAYLAND_DISPLAY").is_ok()
+}
+
+fn tool_available(name: &str) -> bool {
+    Command::new("which")
+        .arg(name)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+// Выбирает, каким инструментом управлять вводом на этой сессии.
+pub fn detect_input_backend() -> InputBackend {
+    #[cfg(not(target_os = "linux"))]
+    {
+        InputBackend::Enigo
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        if is_wayland_session() {
+            if tool_available("ydotool") {
+                return InputBackend::Ydotool;
+            }
+        } else if tool_available("xdotool") {
+            return InputBackend::Xdotool;
+        }
+
+        // Сессия не распозналась или предпочитаемый для неё инструмент не
+        // найден — пробуем оставшиеся варианты по порядку предпочтения.
+        if tool_available("xdotool") {
+            InputBackend::Xdotool
+        } else if tool_available("ydotool") {
+            InputBackend::Ydotool
+        } else {
+            InputBackend::Enigo
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct InputBackendStatus {
+    pub backend: InputBackend,
+    pub session_type: String,
+    pub warning: Option<String>,
+}
+
+// Команда для фронтенда: каким бэкендом сейчас будет воспроизводиться ввод и
+// стоит ли предупредить пользователя, что воспроизведение может не сработать.
+pub fn input_backend_status() -> InputBackendStatus {
+    let backend = detect_input_backend();
+    let session_type = env::var("XDG_SESSION_TYPE").unwrap_or_else(|_| "unknown".to_string());
+
+    let warning = match backend {
+        InputBackend::Xdotool | InputBackend::Enigo if is_wayland_session() => Some(
+            "Wayland-сессия без ydotool: установите ydotool, иначе воспроизведение кликов и клавиш может не работать".to_string(),
+        ),
+        _ => None,
+    };
+
+    InputBackendStatus { backend, session_type, warning }
+}
+
+// Получает текущую позицию курсора выбранным бэкендом. У ydotool/uinput нет
+// команды чтения позиции курсора (Wayland не даёт читать глобальные
+// координаты мыши из соображений безопасности), поэтому на этом бэкенде
+// используем enigo как лучшее доступное приближение.
+pub fn get_cursor_position(backend: InputBackend) -> (i32, i32) {
+    match backend {
+        InputBackend::Xdotool => {
+            let output = Command::new("xdotool").args(&["getmouselocation", "--shell"]).output();
+
+            if let Ok(output) = output {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                let mut x = 0i32;
+                let mut y = 0i32;
+                for line in stdout.lines() {
+                    if let Some(value) = line.strip_prefix("X=") {
+                        x = value.parse().unwrap_or(0);
+                    } else if let Some(value) = line.strip_prefix("Y=") {
+                        y = value.parse().unwrap_or(0);
+                    }
+                }
+                (x, y)
+            } else {
+                (0, 0)
+            }
+        }
+        InputBackend::Ydotool | InputBackend::Enigo => {
+            use enigo::{Enigo, Mouse, Settings};
+            Enigo::new(&Settings::default())
+                .ok()
+                .and_then(|enigo| enigo.location().ok())
+                .unwrap_or((0, 0))
+        }
+    }
+}
+
+// Перемещает курсор в абсолютные координаты выбранным бэкендом.
+pub fn move_mouse_abs(backend: InputBackend, x: i32, y: i32) -> Result<(), String> {
+    match backend {
+        InputBackend::Xdotool => {
+            Command::new("xdotool")
+                .args(&["mousemove", &x.to_string(), &y.to_string()])
+                .status()
+                .map_err(|e| format!("Failed to move mouse via xdotool: {}", e))?;
+        }
+        InputBackend::Ydotool => {
+            Command::new("ydotool")
+                .args(&["mousemove", "--absolute", "-x", &x.to_string(), "-y", &y.to_string()])
+                .status()
+                .map_err(|e| format!("Failed to move mouse via ydotool: {}", e))?;
+        }
+        InputBackend::Enigo => {
+            use enigo::{Enigo, Mouse, Coordinate, Settings};
+            let mut enigo = Enigo::new(&Settings::default())
+                .map_err(|e| format!("Failed to create Enigo: {:?}", e))?;
+            enigo.move_mouse(x, y, Coordinate::Abs)
+                .map_err(|e| format!("Failed to move mouse via enigo: {:?}", e))?;
+        }
+    }
+    Ok(())
+}
+
+// Кликает указанной кнопкой мыши ("left"/"right") выбранным бэкендом.
+pub fn click(backend: InputBackend, button: &str) -> Result<(), String> {
+    match backend {
+        InputBackend::Xdotool => {
+            let button_num = if button == "right" { "3" } else { "1" };
+            Command::new("xdotool")
+                .args(&["click", button_num])
+                .status()
+                .map_err(|e| format!("Failed to click via xdotool: {}", e))?;
+        }
+        InputBackend::Ydotool => {
+            // 0xC0 = BTN_LEFT нажатие+отпускание, 0xC1 = BTN_RIGHT нажатие+отпускание
+            let button_code = if button == "right" { "0xC1" } else { "0xC0" };
+            Command::new("ydotool")
+                .args(&["click", button_code])
+                .status()
+                .map_err(|e| format!("Failed to click via ydotool: {}", e))?;
+        }
+        InputBackend::Enigo => {
+            use enigo::{Enigo, Mouse, Button, Direction, Settings};
+            let mut enigo = Enigo::new(&Settings::default())
+                .map_err(|e| format!("Failed to create Enigo: {:?}", e))?;
+            let btn = if button == "right" { Button::Right } else { Button::Left };
+            enigo.button(btn, Direction::Click)
+                .map_err(|e| format!("Failed to click via enigo: {:?}", e))?;
+        }
+    }
+    Ok(())
+}
+
+// Прокручивает колесо мыши в указанном направлении ("up"/"down").
+pub fn scroll(backend: InputBackend, direction: &str) -> Result<(), String> {
+    match backend {
+        InputBackend::Xdotool => {
+            let clicks_arg = if direction == "up" { "4" } else { "5" };
+            Command::new("xdotool")
+                .args(&["click", clicks_arg])
+                .status()
+                .map_err(|e| format!("Failed to scroll via xdotool: {}", e))?;
+        }
+        InputBackend::Ydotool => {
+            let amount = if direction == "up" { "-3" } else { "3" };
+            Command::new("ydotool")
+                .args(&["mousemove", "--wheel", "--", "0", amount])
+                .status()
+                .map_err(|e| format!("Failed to scroll via ydotool: {}", e))?;
+        }
+        InputBackend::Enigo => {
+            use enigo::{Enigo, Mouse, Axis, Settings};
+            let mut enigo = Enigo::new(&Settings::default())
+                .map_err(|e| format!("Failed to create Enigo: {:?}", e))?;
+            let amount = if direction == "up" { -3 } else { 3 };
+            enigo.scroll(amount, Axis::Vertical)
+                .map_err(|e| format!("Failed to scroll via enigo: {:?}", e))?;
+        }
+    }
+    Ok(())
+}
+
+// Переводит строковое имя клавиши rdev (Debug от rdev::Key, например
+// "Return", "KeyA", "ShiftLeft") в имя клавиши для `xdotool key`.
+fn rdev_key_to_xdotool(key: &str) -> String {
+    match key {
+        "Return" => "Return".to_string(),
+        "Escape" => "Escape".to_string(),
+        "Backspace" => "BackSpace".to_string(),
+        "Tab" => "Tab".to_string(),
+        "Space" => "space".to_string(),
+        "Delete" => "Delete".to_string(),
+        "Home" => "Home".to_string(),
+        "End" => "End".to_string(),
+        "UpArrow" => "Up".to_string(),
+        "DownArrow" => "Down".to_string(),
+        "LeftArrow" => "Left".to_string(),
+        "RightArrow" => "Right".to_string(),
+        "ShiftLeft" | "ShiftRight" => "shift".to_string(),
+        "ControlLeft" | "ControlRight" => "ctrl".to_string(),
+        "Alt" | "AltGr" => "alt".to_string(),
+        "MetaLeft" | "MetaRight" => "super".to_string(),
+        key if key.starts_with("Key") && key.len() == 4 => key[3..].to_lowercase(),
+        key if key.starts_with("Num") && key.len() == 4 => key[3..].to_string(),
+        other => other.to_string(),
+    }
+}
+
+// То же самое, но для enigo::Key. Возвращает None для клавиш без прямого
+// соответствия (в этом случае событие пропускается).
+fn rdev_key_to_enigo(key: &str) -> Option<enigo::Key> {
+    use enigo::Key;
+
+    match key {
+        "Return" => Some(Key::Return),
+        "Escape" => Some(Key::Escape),
+        "Backspace" => Some(Key::Backspace),
+        "Tab" => Some(Key::Tab),
+        "Space" => Some(Key::Space),
+        "Delete" => Some(Key::Delete),
+        "Home" => Some(Key::Home),
+        "End" => Some(Key::End),
+        "UpArrow" => Some(Key::UpArrow),
+        "DownArrow" => Some(Key::DownArrow),
+        "LeftArrow" => Some(Key::LeftArrow),
+        "RightArrow" => Some(Key::RightArrow),
+        "ShiftLeft" | "ShiftRight" => Some(Key::Shift),
+        "ControlLeft" | "ControlRight" => Some(Key::Control),
+        "Alt" | "AltGr" => Some(Key::Alt),
+        "MetaLeft" | "MetaRight" => Some(Key::Meta),
+        key if key.starts_with("Key") && key.len() == 4 => key.chars().last().map(|c| Key::Unicode(c.to_ascii_lowercase())),
+        key if key.starts_with("Num") && key.len() == 4 => key.chars().last().map(Key::Unicode),
+        _ => None,
+    }
+}
+
+// То же самое, но в виде кода клавиши ядра Linux (input-event-codes.h) для
+// `ydotool key <code>:1` (нажатие) / `<code>:0` (отпускание). Раскладка
+// клавиш по умолчанию QWERTY, поэтому буквы не идут по алфавиту.
+fn rdev_key_to_evdev_code(key: &str) -> Option<u32> {
+    match key {
+        "KeyA" => Some(30), "KeyB" => Some(48), "KeyC" => Some(46), "KeyD" => Some(32),
+        "KeyE" => Some(18), "KeyF" => Some(33), "KeyG" => Some(34), "KeyH" => Some(35),
+        "KeyI" => Some(23), "KeyJ" => Some(36), "KeyK" => Some(37), "KeyL" => Some(38),
+        "KeyM" => Some(50), "KeyN" => Some(49), "KeyO" => Some(24), "KeyP" => Some(25),
+        "KeyQ" => Some(16), "KeyR" => Some(19), "KeyS" => Some(31), "KeyT" => Some(20),
+        "KeyU" => Some(22), "KeyV" => Some(47), "KeyW" => Some(17), "KeyX" => Some(45),
+        "KeyY" => Some(21), "KeyZ" => Some(44),
+        "Num1" => Some(2), "Num2" => Some(3), "Num3" => Some(4), "Num4" => Some(5),
+        "Num5" => Some(6), "Num6" => Some(7), "Num7" => Some(8), "Num8" => Some(9),
+        "Num9" => Some(10), "Num0" => Some(11),
+        "Return" => Some(28),
+        "Escape" => Some(1),
+        "Backspace" => Some(14),
+        "Tab" => Some(15),
+        "Space" => Some(57),
+        "Delete" => Some(111),
+        "Home" => Some(102),
+        "End" => Some(107),
+        "UpArrow" => Some(103),
+        "DownArrow" => Some(108),
+        "LeftArrow" => Some(105),
+        "RightArrow" => Some(106),
+        "ShiftLeft" => Some(42),
+        "ShiftRight" => Some(54),
+        "ControlLeft" => Some(29),
+        "ControlRight" => Some(97),
+        "Alt" => Some(56),
+        "AltGr" => Some(100),
+        "MetaLeft" => Some(125),
+        "MetaRight" => Some(126),
+        _ => None,
+    }
+}
+
+// Воспроизводит одно записанное нажатие/отпускание клавиши ("press"/
+// "release") выбранным бэкендом.
+pub fn key_event(backend: InputBackend, key: &str, direction: &str) -> Result<(), String> {
+    match backend {
+        InputBackend::Xdotool => {
+            let xdotool_key = rdev_key_to_xdotool(key);
+            let action = if direction == "release" { "keyup" } else { "keydown" };
+            Command::new("xdotool")
+                .args(&[action, &xdotool_key])
+                .status()
+                .map_err(|e| format!("Failed to send key via xdotool: {}", e))?;
+        }
+        InputBackend::Ydotool => match rdev_key_to_evdev_code(key) {
+            Some(code) => {
+                let state = if direction == "release" { 0 } else { 1 };
+                Command::new("ydotool")
+                    .args(&["key", &format!("{}:{}", code, state)])
+                    .status()
+                    .map_err(|e| format!("Failed to send key via ydotool: {}", e))?;
+            }
+            None => {
+                println!("No ydotool/evdev mapping for key '{}', skipping", key);
+            }
+        },
+        InputBackend::Enigo => {
+            use enigo::{Enigo, Keyboard, Direction, Settings};
+            match rdev_key_to_enigo(key) {
+                Some(enigo_key) => {
+                    let mut enigo = Enigo::new(&Settings::default())
+                        .map_err(|e| format!("Failed to create Enigo: {:?}", e))?;
+                    let dir = if direction == "release" { Direction::Release } else { Direction::Press };
+                    enigo.key(enigo_key, dir).map_err(|e| format!("Failed to send key via enigo: {:?}", e))?;
+                }
+                None => {
+                    println!("No enigo mapping for key '{}', skipping", key);
+                }
+            }
+        }
+    }
+    Ok(())
+}
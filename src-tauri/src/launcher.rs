@@ -0,0 +1,119 @@
+// Модуль для безопасного запуска внешних процессов (браузер, терминал, IDE).
+// Если приложение упаковано в AppImage/Flatpak/Snap, раннер подменяет PATH,
+// LD_LIBRARY_PATH и похожие переменные своими путями, и это окружение
+// протекает в дочерние процессы, из-за чего xdg-open/терминалы/IDE
+// запускаются с чужими библиотеками или вообще не находятся. Поэтому перед
+// spawn любого внешнего процесса нужно прогонять Command через clean_command
+// / clean_tokio_command.
+
+use std::collections::HashSet;
+use std::env;
+
+const PATH_LIKE_VARS: &[&str] = &[
+    "PATH",
+    "LD_LIBRARY_PATH",
+    "GST_PLUGIN_SYSTEM_PATH",
+    "GST_PLUGIN_PATH",
+    "GTK_PATH",
+    "XDG_DATA_DIRS",
+    "XDG_CONFIG_DIRS",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BundleKind {
+    AppImage,
+    Flatpak,
+    Snap,
+    None,
+}
+
+// Определяем, из какого типа сборки мы запущены, и корень её монтирования,
+// чтобы знать, какие куски PATH-подобных переменных ей принадлежат.
+fn detect_bundle() -> (BundleKind, String) {
+    if let Ok(appdir) = env::var("APPDIR") {
+        if env::var("APPIMAGE").is_ok() && !appdir.is_empty() {
+            return (BundleKind::AppImage, appdir);
+        }
+    }
+
+    if std::fs::metadata("/.flatpak-info").is_ok() {
+        return (BundleKind::Flatpak, "/app".to_string());
+    }
+
+    if let Ok(snap_dir) = env::var("SNAP") {
+        if !snap_dir.is_empty() {
+            return (BundleKind::Snap, snap_dir);
+        }
+    }
+
+    (BundleKind::None, String::new())
+}
+
+// Убираем из PATH-подобной переменной все куски, смонтированные сборкой,
+// и заодно убираем дубликаты, сохраняя порядок.
+fn strip_bundle_entries(value: &str, mount_root: &str) -> String {
+    let mut seen = HashSet::new();
+    value
+        .split(':')
+        .filter(|entry| !entry.is_empty() && !entry.starts_with(mount_root))
+        .filter(|entry| seen.insert(*entry))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+// Возвращает переменные окружения, которые нужно установить или убрать,
+// чтобы дочерний процесс не унаследовал PATH/LD_LIBRARY_PATH и т.п. от
+// AppImage/Flatpak/Snap. Если сборщик сохранил исходное значение в
+// `<VAR>_ORIG` (как делают генерируемые AppImage/Flatpak обёртки), мы просто
+// восстанавливаем его вместо того чтобы пытаться вычистить текущее.
+fn sanitized_env() -> (Vec<(String, String)>, Vec<String>) {
+    let mut to_set = Vec::new();
+    let mut to_remove = Vec::new();
+
+    let (bundle, mount_root) = detect_bundle();
+    if bundle == BundleKind::None {
+        return (to_set, to_remove);
+    }
+
+    for var in PATH_LIKE_VARS {
+        let orig_var = format!("{}_ORIG", var);
+
+        let cleaned = match env::var(&orig_var) {
+            Ok(original_value) => original_value,
+            Err(_) => match env::var(var) {
+                Ok(current_value) => strip_bundle_entries(&current_value, &mount_root),
+                Err(_) => continue,
+            },
+        };
+
+        if cleaned.is_empty() {
+            to_remove.push(var.to_string());
+        } else {
+            to_set.push((var.to_string(), cleaned));
+        }
+    }
+
+    (to_set, to_remove)
+}
+
+// Применяем очищенное окружение к std::process::Command перед spawn.
+pub fn clean_command(command: &mut std::process::Command) {
+    let (to_set, to_remove) = sanitized_env();
+    for (key, value) in to_set {
+        command.env(key, value);
+    }
+    for key in to_remove {
+        command.env_remove(key);
+    }
+}
+
+// То же самое, но для tokio::process::Command.
+pub fn clean_tokio_command(command: &mut tokio::process::Command) {
+    let (to_set, to_remove) = sanitized_env();
+    for (key, value) in to_set {
+        command.env(key, value);
+    }
+    for key in to_remove {
+        command.env_remove(key);
+    }
+}
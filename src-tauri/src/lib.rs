@@ -1,3 +1,12 @@
+mod launcher;
+mod vision_provider;
+mod input_backend;
+mod video_pipeline;
+mod websocket_stream;
+mod webtransport_stream;
+
+use vision_provider::VisionProvider;
+
 use tauri::Manager;
 use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
 use std::time::Duration;
@@ -15,10 +24,16 @@ struct AppState {
     window_width: Option<u32>,
     window_height: Option<u32>,
     translation_hotkey: Option<String>,
+    record_toggle_hotkey: Option<String>,
+    replay_hotkey: Option<String>,
     last_route: Option<String>,
     openai_api_key: Option<String>,
     anthropic_api_key: Option<String>,
     auto_open_links: Option<bool>,
+    vision_model: Option<String>,
+    vision_max_tokens: Option<u32>,
+    popup_visible_on_all_workspaces: Option<bool>,
+    popup_always_on_top: Option<bool>,
 }
 
 impl Default for AppState {
@@ -30,10 +45,16 @@ impl Default for AppState {
             window_width: None,
             window_height: None,
             translation_hotkey: None,
+            record_toggle_hotkey: None,
+            replay_hotkey: None,
             last_route: Some("/".to_string()),
             openai_api_key: None,
             anthropic_api_key: None,
             auto_open_links: None,
+            vision_model: None,
+            vision_max_tokens: None,
+            popup_visible_on_all_workspaces: None,
+            popup_always_on_top: None,
         }
     }
 }
@@ -271,24 +292,199 @@ struct PopupState {
     screen_y: Mutex<i32>, // Y координата скриншота на экране
 }
 
-// Структура для записи кликов
+// Единый формат записанного события ввода (как делает rustdesk: один поток
+// событий вместо отдельных структур на мышь/клавиатуру). Раньше это была
+// структура ClickPoint с строковыми полями kind/button на все случаи;
+// тегированный enum не позволяет собрать бессмысленную комбинацию полей.
 #[derive(Serialize, Deserialize, Clone, Debug)]
-struct ClickPoint {
-    x: i32,
-    y: i32,
-    monitor: usize,
-    button: String, // "left" или "right"
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum RecordedEvent {
+    Mouse { x: i32, y: i32, monitor: usize, button: String, timestamp_ms: u64 }, // button: "left"/"right"
+    Scroll { x: i32, y: i32, monitor: usize, direction: String, timestamp_ms: u64 }, // direction: "up"/"down"
+    Key { key: String, direction: String, timestamp_ms: u64 }, // direction: "press"/"release"
+}
+
+impl RecordedEvent {
+    fn timestamp_ms(&self) -> u64 {
+        match self {
+            RecordedEvent::Mouse { timestamp_ms, .. } => *timestamp_ms,
+            RecordedEvent::Scroll { timestamp_ms, .. } => *timestamp_ms,
+            RecordedEvent::Key { timestamp_ms, .. } => *timestamp_ms,
+        }
+    }
 }
 
 // Глобальное состояние для записи кликов
 struct ClickRecordingState {
     is_recording: Mutex<bool>,
-    clicks: std::sync::Arc<Mutex<Vec<ClickPoint>>>,
+    clicks: std::sync::Arc<Mutex<Vec<RecordedEvent>>>,
+}
+
+// Глобальное состояние для отмены текущей конвертации видео
+struct ConversionState {
+    child_pid: Mutex<Option<u32>>,
+}
+
+// Глобальное состояние запущенного сервера трансляции экрана (websocket_stream)
+struct StreamServerState {
+    shutdown: Mutex<Option<std::sync::Arc<tokio::sync::Notify>>>,
+    handle: Mutex<Option<tokio::task::JoinHandle<()>>>,
+}
+
+// Выбор кодека трансляции с фронтенда: JPEG по тику (по умолчанию) или
+// видео через GStreamer (chunk0-4).
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum StreamCodecRequest {
+    Jpeg,
+    H264 { bitrate_kbps: u32 },
+    Vp8 { bitrate_kbps: u32 },
+}
+
+impl From<StreamCodecRequest> for websocket_stream::StreamCodec {
+    fn from(codec: StreamCodecRequest) -> Self {
+        match codec {
+            StreamCodecRequest::Jpeg => websocket_stream::StreamCodec::Jpeg,
+            StreamCodecRequest::H264 { bitrate_kbps } => {
+                websocket_stream::StreamCodec::Video(video_pipeline::VideoCodec::H264 { bitrate_kbps })
+            }
+            StreamCodecRequest::Vp8 { bitrate_kbps } => {
+                websocket_stream::StreamCodec::Video(video_pipeline::VideoCodec::Vp8 { bitrate_kbps })
+            }
+        }
+    }
+}
+
+// Выбор транспорта с фронтенда: обычный TCP/WebSocket (по умолчанию) или
+// QUIC/HTTP3 WebTransport (chunk0-5).
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum TransportRequest {
+    Tcp,
+    WebTransport { port: u16, delivery: FrameDeliveryRequest },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum FrameDeliveryRequest {
+    Datagram,
+    UnidirectionalStream,
+}
+
+impl From<FrameDeliveryRequest> for webtransport_stream::FrameDelivery {
+    fn from(delivery: FrameDeliveryRequest) -> Self {
+        match delivery {
+            FrameDeliveryRequest::Datagram => webtransport_stream::FrameDelivery::Datagram,
+            FrameDeliveryRequest::UnidirectionalStream => webtransport_stream::FrameDelivery::UnidirectionalStream,
+        }
+    }
+}
+
+impl From<TransportRequest> for websocket_stream::Transport {
+    fn from(transport: TransportRequest) -> Self {
+        match transport {
+            TransportRequest::Tcp => websocket_stream::Transport::Tcp,
+            TransportRequest::WebTransport { port, delivery } => {
+                websocket_stream::Transport::WebTransport { port, delivery: delivery.into() }
+            }
+        }
+    }
+}
+
+// Сертификат/ключ для wss://, присланные с фронтенда как пути к файлам на
+// диске (chunk0-1); DER-вариант TlsConfig через эту команду не передаётся.
+#[derive(Deserialize)]
+struct TlsConfigRequest {
+    cert_path: String,
+    key_path: String,
+}
+
+impl From<TlsConfigRequest> for websocket_stream::TlsConfig {
+    fn from(tls: TlsConfigRequest) -> Self {
+        websocket_stream::TlsConfig::Files { cert_path: tls.cert_path, key_path: tls.key_path }
+    }
+}
+
+// Команда для запуска сервера трансляции экрана по WebSocket/HTTP. codec,
+// transport и tls необязательны и по умолчанию дают прежнее поведение
+// (JPEG по обычному TCP без TLS), но позволяют фронтенду реально выбрать
+// видеокодек, WebTransport или wss://, а не только значения по умолчанию.
+#[tauri::command]
+async fn start_screen_stream(
+    state: tauri::State<'_, StreamServerState>,
+    port: u16,
+    screen_index: usize,
+    remote_input_enabled: bool,
+    codec: Option<StreamCodecRequest>,
+    transport: Option<TransportRequest>,
+    tls: Option<TlsConfigRequest>,
+) -> Result<(), String> {
+    {
+        let already_running = state.shutdown.lock().unwrap().is_some();
+        if already_running {
+            return Err("Stream server is already running".to_string());
+        }
+    }
+
+    let shutdown = std::sync::Arc::new(tokio::sync::Notify::new());
+
+    let codec = codec.map(Into::into).unwrap_or(websocket_stream::StreamCodec::Jpeg);
+    let transport = transport.map(Into::into).unwrap_or(websocket_stream::Transport::Tcp);
+    let tls_config = tls.map(Into::into);
+
+    let handle = websocket_stream::start_websocket_server(
+        port,
+        screen_index,
+        shutdown.clone(),
+        tls_config,
+        Duration::from_secs(30),
+        codec,
+        transport,
+        remote_input_enabled,
+    )
+    .await?;
+
+    *state.shutdown.lock().unwrap() = Some(shutdown);
+    *state.handle.lock().unwrap() = Some(handle);
+
+    println!("Screen stream server started on port {}", port);
+    Ok(())
+}
+
+// Команда для остановки сервера трансляции экрана
+#[tauri::command]
+fn stop_screen_stream(state: tauri::State<'_, StreamServerState>) -> Result<(), String> {
+    let shutdown = state.shutdown.lock().unwrap().take();
+
+    match shutdown {
+        Some(shutdown) => {
+            shutdown.notify_waiters();
+            *state.handle.lock().unwrap() = None;
+            println!("Screen stream server stop requested");
+            Ok(())
+        }
+        None => Err("Stream server is not running".to_string()),
+    }
 }
 
 // Глобальная переменная для остановки записи
 static STOP_RECORDING: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
 
+// Глобальная переменная для остановки воспроизведения
+static STOP_REPLAY: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+// Получает текущую позицию курсора через выбранный для этой сессии бэкенд ввода
+fn get_cursor_position() -> (i32, i32) {
+    input_backend::get_cursor_position(input_backend::detect_input_backend())
+}
+
+// Команда для фронтенда: каким бэкендом будет воспроизводиться ввод и стоит
+// ли предупредить пользователя (например, Wayland без ydotool)
+#[tauri::command]
+fn get_input_backend_status() -> input_backend::InputBackendStatus {
+    input_backend::input_backend_status()
+}
+
 // Команда для получения сохранённого скриншота по индексу монитора
 #[tauri::command]
 fn get_stored_screenshot(monitor_index: usize, state: tauri::State<ScreenshotState>) -> Result<String, String> {
@@ -532,6 +728,10 @@ async fn open_translation_popup(app_handle: tauri::AppHandle, x: i32, y: i32, wi
     let popup_x = x - content_padding;
     let popup_y = y - header_height - content_padding - 9;
 
+    // Подхватываем сохранённые флаги поведения popup, чтобы они применялись
+    // сразу при создании, а не только после ручного переключения
+    let saved_state = load_state();
+
     // Создаём окно за пределами экрана чтобы избежать анимации compositor
     let webview_window = WebviewWindowBuilder::new(
         &app_handle,
@@ -543,7 +743,8 @@ async fn open_translation_popup(app_handle: tauri::AppHandle, x: i32, y: i32, wi
     .inner_size(popup_width as f64, popup_height as f64)
     .decorations(false)
     .transparent(true)
-    .always_on_top(false)
+    .always_on_top(saved_state.popup_always_on_top.unwrap_or(false))
+    .visible_on_all_workspaces(saved_state.popup_visible_on_all_workspaces.unwrap_or(false))
     .skip_taskbar(false)
     .visible(true)
     .resizable(true)
@@ -576,6 +777,38 @@ fn get_popup_screen_position(popup_state: tauri::State<'_, PopupState>) -> (i32,
     (x, y)
 }
 
+// Команда для закрепления popup на всех виртуальных рабочих столах
+#[tauri::command]
+fn set_popup_visible_on_all_workspaces(app_handle: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    let mut state = load_state();
+    state.popup_visible_on_all_workspaces = Some(enabled);
+    save_state(&state);
+
+    if let Some(popup) = app_handle.get_webview_window("translation-popup") {
+        popup
+            .set_visible_on_all_workspaces(enabled)
+            .map_err(|e| format!("Failed to set visible_on_all_workspaces: {}", e))?;
+    }
+
+    Ok(())
+}
+
+// Команда для закрепления popup поверх остальных окон
+#[tauri::command]
+fn set_popup_always_on_top(app_handle: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    let mut state = load_state();
+    state.popup_always_on_top = Some(enabled);
+    save_state(&state);
+
+    if let Some(popup) = app_handle.get_webview_window("translation-popup") {
+        popup
+            .set_always_on_top(enabled)
+            .map_err(|e| format!("Failed to set always_on_top: {}", e))?;
+    }
+
+    Ok(())
+}
+
 // Команда для закрытия popup окна
 #[tauri::command]
 async fn close_translation_popup(app_handle: tauri::AppHandle) -> Result<(), String> {
@@ -716,13 +949,55 @@ async fn set_window_size(app_handle: tauri::AppHandle, width: u32, height: u32)
 
 // Команда для обработки выбранной области - вырезает из сохранённого скриншота
 #[tauri::command]
-async fn capture_area_screenshot(x: u32, y: u32, width: u32, height: u32, monitor_index: usize, state: tauri::State<'_, ScreenshotState>) -> Result<String, String> {
-    use png::Encoder;
-    use png::ColorType;
-    use std::io::BufWriter;
-
+async fn capture_area_screenshot(
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    monitor_index: usize,
+    format: Option<ImageFormat>,
+    quality: Option<u8>,
+    state: tauri::State<'_, ScreenshotState>,
+) -> Result<String, String> {
     println!("Cutting area from saved screenshot: x={}, y={}, width={}, height={}, monitor={}", x, y, width, height, monitor_index);
 
+    let cropped_data = crop_monitor_rgba(&state, monitor_index, x, y, width, height)?;
+
+    let format = format.unwrap_or(ImageFormat::Png);
+    let quality = quality.unwrap_or(90).clamp(1, 100);
+
+    let encoded = match format {
+        ImageFormat::Png => encode_png_with_capture_metadata(&cropped_data, width, height, monitor_index, x, y)?,
+        ImageFormat::Jpeg => encode_rgba_as_jpeg(&cropped_data, width, height, quality)?,
+        ImageFormat::Webp => encode_rgba_as_webp(&cropped_data, width, height, quality)?,
+    };
+
+    let base64_image = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &encoded);
+
+    println!("Area screenshot cut successfully from saved screenshot ({:?})", format);
+    Ok(base64_image)
+}
+
+// Формат вывода для обрезанного скриншота области
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ImageFormat {
+    Png,
+    Jpeg,
+    Webp,
+}
+
+// Достаёт сохранённый скриншот монитора из ScreenshotState, декодирует PNG и
+// вырезает нужную область в сырые RGBA-байты. Общая логика для
+// capture_area_screenshot и copy_area_to_clipboard.
+fn crop_monitor_rgba(
+    state: &ScreenshotState,
+    monitor_index: usize,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>, String> {
     // Получаем сохранённый скриншот для указанного монитора
     let screenshots = state.data.lock().unwrap();
     let base64_screenshot = screenshots.get(&monitor_index)
@@ -761,7 +1036,28 @@ async fn capture_area_screenshot(x: u32, y: u32, width: u32, height: u32, monito
         cropped_data.extend_from_slice(&full_rgba_data[start_idx..end_idx]);
     }
 
-    // Кодируем обрезанное изображение в PNG
+    Ok(cropped_data)
+}
+
+// Кодирует вырезанную область в PNG и встраивает метаданные захвата
+// (монитор, время, прямоугольник выделения) как tEXt-чанки для provenance
+fn encode_png_with_capture_metadata(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    monitor_index: usize,
+    x: u32,
+    y: u32,
+) -> Result<Vec<u8>, String> {
+    use png::Encoder;
+    use png::ColorType;
+    use std::io::BufWriter;
+
+    let captured_at_unix = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
     let mut result_png_data = Vec::new();
     {
         let w = BufWriter::new(&mut result_png_data);
@@ -769,18 +1065,85 @@ async fn capture_area_screenshot(x: u32, y: u32, width: u32, height: u32, monito
         encoder.set_color(ColorType::Rgba);
         encoder.set_depth(png::BitDepth::Eight);
 
+        encoder.add_text_chunk("Software".to_string(), "bro".to_string())
+            .map_err(|e| format!("Failed to write PNG metadata: {}", e))?;
+        encoder.add_text_chunk("Monitor".to_string(), monitor_index.to_string())
+            .map_err(|e| format!("Failed to write PNG metadata: {}", e))?;
+        encoder.add_text_chunk("CapturedAtUnix".to_string(), captured_at_unix.to_string())
+            .map_err(|e| format!("Failed to write PNG metadata: {}", e))?;
+        encoder.add_text_chunk("SelectionRect".to_string(), format!("{},{},{},{}", x, y, width, height))
+            .map_err(|e| format!("Failed to write PNG metadata: {}", e))?;
+
         let mut writer = encoder.write_header()
             .map_err(|e| format!("Failed to write PNG header: {}", e))?;
 
-        writer.write_image_data(&cropped_data)
+        writer.write_image_data(rgba)
             .map_err(|e| format!("Failed to write PNG data: {}", e))?;
     }
 
-    // Конвертируем в base64
-    let base64_image = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &result_png_data);
+    Ok(result_png_data)
+}
 
-    println!("Area screenshot cut successfully from saved screenshot");
-    Ok(base64_image)
+// JPEG не умеет в альфа-канал, поэтому сначала схлопываем RGBA в RGB
+fn encode_rgba_as_jpeg(rgba: &[u8], width: u32, height: u32, quality: u8) -> Result<Vec<u8>, String> {
+    use image::ImageEncoder;
+    use image::codecs::jpeg::JpegEncoder;
+
+    let pixel_count = (width * height) as usize;
+    let mut rgb_data = Vec::with_capacity(pixel_count * 3);
+    for chunk in rgba.chunks_exact(4) {
+        rgb_data.extend_from_slice(&chunk[..3]);
+    }
+
+    let mut buffer = Vec::new();
+    let encoder = JpegEncoder::new_with_quality(&mut buffer, quality);
+    encoder
+        .write_image(&rgb_data, width, height, image::ExtendedColorType::Rgb8)
+        .map_err(|e| format!("Failed to encode JPEG: {}", e))?;
+
+    Ok(buffer)
+}
+
+// `quality` is accepted for symmetry with encode_rgba_as_jpeg and so the
+// call site makes clear it was considered, but the `image` crate's WebP
+// encoder only supports lossless encoding (there's no lossy/quality knob in
+// its API) — so it's a deliberate no-op here, not a dropped parameter.
+fn encode_rgba_as_webp(rgba: &[u8], width: u32, height: u32, _quality: u8) -> Result<Vec<u8>, String> {
+    use image::ImageEncoder;
+    use image::codecs::webp::WebPEncoder;
+
+    let mut buffer = Vec::new();
+    let encoder = WebPEncoder::new_lossless(&mut buffer);
+    encoder
+        .write_image(rgba, width, height, image::ExtendedColorType::Rgba8)
+        .map_err(|e| format!("Failed to encode WebP: {}", e))?;
+
+    Ok(buffer)
+}
+
+// Команда для копирования вырезанной области прямо в системный буфер обмена
+#[tauri::command]
+async fn copy_area_to_clipboard(x: u32, y: u32, width: u32, height: u32, monitor_index: usize, state: tauri::State<'_, ScreenshotState>) -> Result<(), String> {
+    use arboard::{Clipboard, ImageData};
+    use std::borrow::Cow;
+
+    println!("Copying area to clipboard: x={}, y={}, width={}, height={}, monitor={}", x, y, width, height, monitor_index);
+
+    let cropped_data = crop_monitor_rgba(&state, monitor_index, x, y, width, height)?;
+
+    let mut clipboard = Clipboard::new()
+        .map_err(|e| format!("Failed to access clipboard: {}", e))?;
+
+    clipboard
+        .set_image(ImageData {
+            width: width as usize,
+            height: height as usize,
+            bytes: Cow::Owned(cropped_data),
+        })
+        .map_err(|e| format!("Failed to copy image to clipboard: {}", e))?;
+
+    println!("Area copied to clipboard successfully");
+    Ok(())
 }
 
 // Команда для сохранения горячей клавиши
@@ -800,6 +1163,40 @@ fn get_translation_hotkey() -> Result<Option<String>, String> {
     Ok(state.translation_hotkey)
 }
 
+// Команда для сохранения горячей клавиши переключения записи кликов
+#[tauri::command]
+fn save_record_toggle_hotkey(hotkey: String) -> Result<(), String> {
+    let mut state = load_state();
+    state.record_toggle_hotkey = Some(hotkey.clone());
+    save_state(&state);
+    println!("Record toggle hotkey saved: {}", hotkey);
+    Ok(())
+}
+
+// Команда для получения сохранённой горячей клавиши переключения записи кликов
+#[tauri::command]
+fn get_record_toggle_hotkey() -> Result<Option<String>, String> {
+    let state = load_state();
+    Ok(state.record_toggle_hotkey)
+}
+
+// Команда для сохранения горячей клавиши воспроизведения записи
+#[tauri::command]
+fn save_replay_hotkey(hotkey: String) -> Result<(), String> {
+    let mut state = load_state();
+    state.replay_hotkey = Some(hotkey.clone());
+    save_state(&state);
+    println!("Replay hotkey saved: {}", hotkey);
+    Ok(())
+}
+
+// Команда для получения сохранённой горячей клавиши воспроизведения записи
+#[tauri::command]
+fn get_replay_hotkey() -> Result<Option<String>, String> {
+    let state = load_state();
+    Ok(state.replay_hotkey)
+}
+
 // Команда для сохранения последнего маршрута
 #[tauri::command]
 fn save_last_route(route: String) -> Result<(), String> {
@@ -886,148 +1283,114 @@ async fn open_url_in_browser(url: String) -> Result<(), String> {
 
     #[cfg(target_os = "linux")]
     {
-        Command::new("xdg-open")
-            .arg(&full_url)
-            .process_group(0)
-            .spawn()
-            .map_err(|e| format!("Failed to open URL: {}", e))?;
+        let mut cmd = Command::new("xdg-open");
+        cmd.arg(&full_url).process_group(0);
+        crate::launcher::clean_command(&mut cmd);
+        cmd.spawn().map_err(|e| format!("Failed to open URL: {}", e))?;
     }
 
     #[cfg(target_os = "windows")]
     {
-        Command::new("cmd")
-            .args(&["/c", "start", "", &full_url])
-            .spawn()
-            .map_err(|e| format!("Failed to open URL: {}", e))?;
+        let mut cmd = Command::new("cmd");
+        cmd.args(&["/c", "start", "", &full_url]);
+        crate::launcher::clean_command(&mut cmd);
+        cmd.spawn().map_err(|e| format!("Failed to open URL: {}", e))?;
     }
 
     #[cfg(target_os = "macos")]
     {
-        Command::new("open")
-            .arg(&full_url)
-            .spawn()
-            .map_err(|e| format!("Failed to open URL: {}", e))?;
+        let mut cmd = Command::new("open");
+        cmd.arg(&full_url);
+        crate::launcher::clean_command(&mut cmd);
+        cmd.spawn().map_err(|e| format!("Failed to open URL: {}", e))?;
     }
 
     println!("URL opened successfully");
     Ok(())
 }
 
-// Команда для отправки изображения в ChatGPT
+// Команда для отправки изображения провайдеру без привязки к конкретному API.
+// Заменяет старые send_to_chatgpt/send_to_claude: оба были почти одинаковым
+// кодом с захардкоженной моделью, теперь это один путь через VisionProvider.
 #[tauri::command]
-async fn send_to_chatgpt(api_key: String, image_base64: String, prompt: String) -> Result<String, String> {
-    use reqwest;
-
-    println!("Sending to ChatGPT...");
-
-    let client = reqwest::Client::new();
-
-    let request_body = serde_json::json!({
-        "model": "gpt-4o",
-        "messages": [
-            {
-                "role": "user",
-                "content": [
-                    {
-                        "type": "text",
-                        "text": prompt
-                    },
-                    {
-                        "type": "image_url",
-                        "image_url": {
-                            "url": format!("data:image/png;base64,{}", image_base64)
-                        }
-                    }
-                ]
-            }
-        ],
-        "max_tokens": 1000
-    });
+async fn send_to_provider(
+    provider: VisionProvider,
+    api_key: String,
+    model: String,
+    max_tokens: u32,
+    image_base64: String,
+    prompt: String,
+) -> Result<String, String> {
+    println!("Sending to {:?} ({})...", provider, model);
+    let content = provider.send(&api_key, &model, max_tokens, &image_base64, &prompt).await?;
+    println!("{:?} response received", provider);
+    Ok(content)
+}
 
-    let response = client
-        .post("https://api.openai.com/v1/chat/completions")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .json(&request_body)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to send request: {}", e))?;
+// Стриминговый вариант send_to_provider: токены уходят во фронтенд событием
+// llm-token по мере поступления, а не одним блоком в конце
+#[tauri::command]
+async fn stream_to_provider(
+    app_handle: tauri::AppHandle,
+    provider: VisionProvider,
+    api_key: String,
+    model: String,
+    max_tokens: u32,
+    image_base64: String,
+    prompt: String,
+) -> Result<(), String> {
+    use tauri::Emitter;
 
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-        return Err(format!("ChatGPT API error: {}", error_text));
-    }
+    println!("Streaming from {:?} ({})...", provider, model);
 
-    let response_json: serde_json::Value = response.json().await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
+    let main_window = app_handle
+        .get_webview_window("main")
+        .ok_or("Main window not found")?;
 
-    let content = response_json["choices"][0]["message"]["content"]
-        .as_str()
-        .ok_or("No content in response")?
-        .to_string();
+    provider
+        .stream(&api_key, &model, max_tokens, &image_base64, &prompt, |token| {
+            let _ = main_window.emit("llm-token", token);
+        })
+        .await?;
 
-    println!("ChatGPT response received");
-    Ok(content)
+    main_window
+        .emit("llm-token-done", ())
+        .map_err(|e| format!("Failed to emit event: {}", e))?;
+
+    println!("{:?} stream finished", provider);
+    Ok(())
 }
 
-// Команда для отправки изображения в Claude
+// Команда для сохранения выбранной vision-модели
 #[tauri::command]
-async fn send_to_claude(api_key: String, image_base64: String, prompt: String) -> Result<String, String> {
-    use reqwest;
-
-    println!("Sending to Claude...");
-
-    let client = reqwest::Client::new();
-
-    let request_body = serde_json::json!({
-        "model": "claude-sonnet-4-20250514",
-        "max_tokens": 1024,
-        "messages": [
-            {
-                "role": "user",
-                "content": [
-                    {
-                        "type": "image",
-                        "source": {
-                            "type": "base64",
-                            "media_type": "image/png",
-                            "data": image_base64
-                        }
-                    },
-                    {
-                        "type": "text",
-                        "text": prompt
-                    }
-                ]
-            }
-        ]
-    });
-
-    let response = client
-        .post("https://api.anthropic.com/v1/messages")
-        .header("x-api-key", api_key)
-        .header("anthropic-version", "2023-06-01")
-        .header("Content-Type", "application/json")
-        .json(&request_body)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to send request: {}", e))?;
-
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-        return Err(format!("Claude API error: {}", error_text));
-    }
+fn save_vision_model(model: String) -> Result<(), String> {
+    let mut state = load_state();
+    state.vision_model = Some(model);
+    save_state(&state);
+    Ok(())
+}
 
-    let response_json: serde_json::Value = response.json().await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
+// Команда для получения сохранённой vision-модели
+#[tauri::command]
+fn get_vision_model() -> Result<Option<String>, String> {
+    let state = load_state();
+    Ok(state.vision_model)
+}
 
-    let content = response_json["content"][0]["text"]
-        .as_str()
-        .ok_or("No content in response")?
-        .to_string();
+// Команда для сохранения max_tokens для vision-запросов
+#[tauri::command]
+fn save_vision_max_tokens(max_tokens: u32) -> Result<(), String> {
+    let mut state = load_state();
+    state.vision_max_tokens = Some(max_tokens);
+    save_state(&state);
+    Ok(())
+}
 
-    println!("Claude response received");
-    Ok(content)
+// Команда для получения сохранённого max_tokens для vision-запросов
+#[tauri::command]
+fn get_vision_max_tokens() -> Result<Option<u32>, String> {
+    let state = load_state();
+    Ok(state.vision_max_tokens)
 }
 
 // Команда для эмуляции нажатий клавиш
@@ -1100,10 +1463,11 @@ async fn open_terminal(command: String) -> Result<(), String> {
         ];
 
         for (terminal, args) in terminals {
-            match Command::new(terminal)
-                .args(&args)
-                .spawn()
-            {
+            let mut cmd = Command::new(terminal);
+            cmd.args(&args);
+            crate::launcher::clean_tokio_command(&mut cmd);
+
+            match cmd.spawn() {
                 Ok(_) => {
                     println!("Successfully opened {} terminal", terminal);
                     return Ok(());
@@ -1118,10 +1482,11 @@ async fn open_terminal(command: String) -> Result<(), String> {
     #[cfg(target_os = "windows")]
     {
         // Windows Terminal или CMD
-        match Command::new("cmd")
-            .args(&["/c", "start", "cmd", "/k", &command])
-            .spawn()
-        {
+        let mut cmd = Command::new("cmd");
+        cmd.args(&["/c", "start", "cmd", "/k", &command]);
+        crate::launcher::clean_tokio_command(&mut cmd);
+
+        match cmd.spawn() {
             Ok(_) => {
                 println!("Successfully opened Windows terminal");
                 return Ok(());
@@ -1141,10 +1506,11 @@ async fn open_terminal(command: String) -> Result<(), String> {
             command.replace("\"", "\\\"")
         );
 
-        match Command::new("osascript")
-            .args(&["-e", &script])
-            .spawn()
-        {
+        let mut cmd = Command::new("osascript");
+        cmd.args(&["-e", &script]);
+        crate::launcher::clean_tokio_command(&mut cmd);
+
+        match cmd.spawn() {
             Ok(_) => {
                 println!("Successfully opened macOS terminal");
                 return Ok(());
@@ -1262,19 +1628,18 @@ async fn open_jetbrains_project(project_path: String, ide_name: String) -> Resul
     // Запускаем IDE с проектом как независимый процесс
     #[cfg(unix)]
     {
-        Command::new(ide_command)
-            .arg(&project_path)
-            .process_group(0)  // Создаём новую группу процессов
-            .spawn()
-            .map_err(|e| format!("Failed to open project: {}", e))?;
+        let mut cmd = Command::new(ide_command);
+        cmd.arg(&project_path).process_group(0); // Создаём новую группу процессов
+        crate::launcher::clean_command(&mut cmd);
+        cmd.spawn().map_err(|e| format!("Failed to open project: {}", e))?;
     }
 
     #[cfg(not(unix))]
     {
-        Command::new(ide_command)
-            .arg(&project_path)
-            .spawn()
-            .map_err(|e| format!("Failed to open project: {}", e))?;
+        let mut cmd = Command::new(ide_command);
+        cmd.arg(&project_path);
+        crate::launcher::clean_command(&mut cmd);
+        cmd.spawn().map_err(|e| format!("Failed to open project: {}", e))?;
     }
 
     println!("Project opened successfully");
@@ -1338,97 +1703,255 @@ async fn convert_to_mp4(input_path: String) -> Result<String, String> {
     Ok(output_path)
 }
 
-// Команда для начала записи кликов
+// Целевой формат конвертации видео с параметрами качества под каждый кодек
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "format", rename_all = "snake_case")]
+enum VideoTarget {
+    Mp4 { crf: u8 },
+    Webm { crf: u8 },
+    Gif { fps: u8 },
+}
+
+impl VideoTarget {
+    fn extension(&self) -> &'static str {
+        match self {
+            VideoTarget::Mp4 { .. } => "mp4",
+            VideoTarget::Webm { .. } => "webm",
+            VideoTarget::Gif { .. } => "gif",
+        }
+    }
+
+    fn ffmpeg_args(&self) -> Vec<String> {
+        match self {
+            VideoTarget::Mp4 { crf } => vec![
+                "-c:v".to_string(), "libx264".to_string(),
+                "-crf".to_string(), crf.to_string(),
+                "-c:a".to_string(), "aac".to_string(),
+                "-b:a".to_string(), "192k".to_string(),
+            ],
+            VideoTarget::Webm { crf } => vec![
+                "-c:v".to_string(), "libvpx-vp9".to_string(),
+                "-crf".to_string(), crf.to_string(),
+                "-b:v".to_string(), "0".to_string(),
+                "-c:a".to_string(), "libopus".to_string(),
+            ],
+            VideoTarget::Gif { fps } => vec![
+                "-vf".to_string(), format!("fps={},scale=iw:-1:flags=lanczos", fps),
+                "-loop".to_string(), "0".to_string(),
+            ],
+        }
+    }
+}
+
+// Узнаём длительность исходника через ffprobe -show_format, чтобы считать
+// прогресс конвертации в процентах
+async fn probe_duration_secs(path: &str) -> Option<f64> {
+    use tokio::process::Command;
+
+    let output = Command::new("ffprobe")
+        .args(&["-v", "error", "-show_format", path])
+        .output()
+        .await
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for line in stdout.lines() {
+        if let Some(value) = line.strip_prefix("duration=") {
+            if let Ok(duration) = value.trim().parse::<f64>() {
+                return Some(duration);
+            }
+        }
+    }
+
+    None
+}
+
+// Команда для конвертации видео с выбором формата и прогрессом в реальном времени
 #[tauri::command]
-async fn start_click_recording(state: tauri::State<'_, ClickRecordingState>) -> Result<(), String> {
-    println!("Starting click recording...");
+async fn convert_video(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, ConversionState>,
+    input_path: String,
+    target: VideoTarget,
+) -> Result<String, String> {
+    use tokio::process::Command;
+    use tokio::io::{AsyncBufReadExt, BufReader};
+    use std::path::Path;
+    use std::process::Stdio;
+    use tauri::Emitter;
 
-    // Очищаем предыдущую запись
-    {
-        let mut clicks = state.clicks.lock().unwrap();
-        clicks.clear();
+    println!("Converting {} to {:?}", input_path, target);
+
+    if !Path::new(&input_path).exists() {
+        return Err("Входной файл не найден".to_string());
     }
 
-    // Устанавливаем флаг записи
+    let ffmpeg_check = Command::new("ffmpeg").arg("-version").output().await;
+    if ffmpeg_check.is_err() {
+        return Err("FFmpeg не установлен. Установите FFmpeg для конвертации видео.".to_string());
+    }
+
+    let output_path = Path::new(&input_path)
+        .with_extension(target.extension())
+        .to_string_lossy()
+        .to_string();
+
+    let duration_secs = probe_duration_secs(&input_path).await.unwrap_or(0.0);
+
+    let mut args = vec!["-y".to_string(), "-i".to_string(), input_path.clone()];
+    args.extend(target.ffmpeg_args());
+    args.extend([
+        "-progress".to_string(), "pipe:1".to_string(),
+        "-nostats".to_string(),
+        output_path.clone(),
+    ]);
+
+    let mut child = Command::new("ffmpeg")
+        .args(&args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Ошибка запуска FFmpeg: {}", e))?;
+
+    if let Some(pid) = child.id() {
+        *state.child_pid.lock().unwrap() = Some(pid);
+    }
+
+    let stdout = child.stdout.take().ok_or("Failed to capture ffmpeg stdout")?;
+    let mut lines = BufReader::new(stdout).lines();
+
+    let main_window = app_handle.get_webview_window("main");
+    let mut out_time_ms: u64 = 0;
+    let mut total_size: u64 = 0;
+
+    while let Some(line) = lines
+        .next_line()
+        .await
+        .map_err(|e| format!("Failed to read ffmpeg progress: {}", e))?
     {
-        let mut is_recording = state.is_recording.lock().unwrap();
-        *is_recording = true;
+        if let Some(value) = line.strip_prefix("out_time_ms=") {
+            out_time_ms = value.trim().parse().unwrap_or(out_time_ms);
+        } else if let Some(value) = line.strip_prefix("total_size=") {
+            total_size = value.trim().parse().unwrap_or(total_size);
+        } else if let Some(value) = line.strip_prefix("progress=") {
+            let percent = if duration_secs > 0.0 {
+                ((out_time_ms as f64 / 1_000_000.0) / duration_secs * 100.0).clamp(0.0, 100.0)
+            } else {
+                0.0
+            };
+
+            if let Some(window) = &main_window {
+                let _ = window.emit("convert-progress", serde_json::json!({
+                    "percent": percent,
+                    "outTimeMs": out_time_ms,
+                    "totalSize": total_size,
+                    "done": value.trim() == "end"
+                }));
+            }
+        }
     }
 
-    STOP_RECORDING.store(false, std::sync::atomic::Ordering::SeqCst);
+    let status = child.wait().await.map_err(|e| format!("Ошибка ожидания FFmpeg: {}", e))?;
+    *state.child_pid.lock().unwrap() = None;
+
+    if !status.success() {
+        let _ = tokio::fs::remove_file(&output_path).await;
+        return Err("Ошибка конвертации видео (отменена или завершилась с ошибкой)".to_string());
+    }
+
+    println!("Conversion completed successfully: {}", output_path);
+    Ok(output_path)
+}
+
+// Команда для отмены текущей конвертации видео
+#[tauri::command]
+async fn cancel_conversion(state: tauri::State<'_, ConversionState>) -> Result<(), String> {
+    use tokio::process::Command;
+
+    let pid = state.child_pid.lock().unwrap().take();
 
-    // Клонируем Arc для передачи в поток
-    let clicks_arc = state.clicks.clone();
+    if let Some(pid) = pid {
+        println!("Cancelling conversion (pid {})", pid);
+
+        #[cfg(unix)]
+        {
+            let _ = Command::new("kill").arg("-TERM").arg(pid.to_string()).status().await;
+        }
+
+        #[cfg(windows)]
+        {
+            let _ = Command::new("taskkill").args(&["/PID", &pid.to_string(), "/F"]).status().await;
+        }
+    }
+
+    Ok(())
+}
+
+// Запускает фоновый rdev-листенер, который пишет события в clicks_arc.
+// Общая логика для start_click_recording и hotkey-тумблера из setup().
+fn begin_click_recording(clicks_arc: std::sync::Arc<Mutex<Vec<RecordedEvent>>>) {
+    STOP_RECORDING.store(false, std::sync::atomic::Ordering::SeqCst);
 
     tokio::task::spawn_blocking(move || {
         use rdev::{listen, Event, EventType};
 
+        let recording_start = std::time::Instant::now();
+
+        // Индекс монитора, на котором лежит точка (0, если не найден)
+        let monitor_at = |x: i32, y: i32| -> usize {
+            Screen::all()
+                .ok()
+                .and_then(|screens| screens.iter().position(|screen| {
+                    let info = &screen.display_info;
+                    x >= info.x && x < info.x + info.width as i32 && y >= info.y && y < info.y + info.height as i32
+                }))
+                .unwrap_or(0)
+        };
+
         let callback = move |event: Event| {
             if STOP_RECORDING.load(std::sync::atomic::Ordering::SeqCst) {
                 return;
             }
 
-            // Записываем левые и правые клики мыши
-            let button = match event.event_type {
-                EventType::ButtonPress(rdev::Button::Left) => Some("left"),
-                EventType::ButtonPress(rdev::Button::Right) => Some("right"),
+            let timestamp_ms = recording_start.elapsed().as_millis() as u64;
+
+            // Записываем клики мыши, нажатия/отпускания клавиш и прокрутку колеса
+            let recorded = match event.event_type {
+                EventType::ButtonPress(rdev::Button::Left) | EventType::ButtonPress(rdev::Button::Right) => {
+                    let button = match event.event_type {
+                        EventType::ButtonPress(rdev::Button::Right) => "right".to_string(),
+                        _ => "left".to_string(),
+                    };
+                    let (x, y) = get_cursor_position();
+                    println!("Event recorded: mouse button={}, x={}, y={}", button, x, y);
+                    Some(RecordedEvent::Mouse { x, y, monitor: monitor_at(x, y), button, timestamp_ms })
+                }
+                EventType::KeyPress(key) => {
+                    let key = format!("{:?}", key);
+                    println!("Event recorded: key press {}", key);
+                    Some(RecordedEvent::Key { key, direction: "press".to_string(), timestamp_ms })
+                }
+                EventType::KeyRelease(key) => {
+                    let key = format!("{:?}", key);
+                    println!("Event recorded: key release {}", key);
+                    Some(RecordedEvent::Key { key, direction: "release".to_string(), timestamp_ms })
+                }
+                EventType::Wheel { delta_y, .. } if delta_y != 0 => {
+                    let direction = if delta_y > 0 { "up".to_string() } else { "down".to_string() };
+                    let (x, y) = get_cursor_position();
+                    println!("Event recorded: scroll {}", direction);
+                    Some(RecordedEvent::Scroll { x, y, monitor: monitor_at(x, y), direction, timestamp_ms })
+                }
                 _ => None,
             };
 
-            if let Some(btn) = button {
-                // Получаем позицию из события rdev (более точные координаты)
-                let (x, y) = {
-                    // Используем xdotool для более точных координат на Linux
-                    #[cfg(target_os = "linux")]
-                    {
-                        use std::process::Command;
-                        let output = Command::new("xdotool")
-                            .args(&["getmouselocation", "--shell"])
-                            .output();
-
-                        if let Ok(output) = output {
-                            let stdout = String::from_utf8_lossy(&output.stdout);
-                            let mut x_val = 0i32;
-                            let mut y_val = 0i32;
-
-                            for line in stdout.lines() {
-                                if line.starts_with("X=") {
-                                    x_val = line[2..].parse().unwrap_or(0);
-                                } else if line.starts_with("Y=") {
-                                    y_val = line[2..].parse().unwrap_or(0);
-                                }
-                            }
-                            (x_val, y_val)
-                        } else {
-                            // Fallback к enigo
-                            use enigo::{Enigo, Mouse, Settings};
-                            match Enigo::new(&Settings::default()) {
-                                Ok(enigo) => enigo.location().unwrap_or((0, 0)),
-                                Err(_) => (0, 0),
-                            }
-                        }
-                    }
-
-                    #[cfg(not(target_os = "linux"))]
-                    {
-                        use enigo::{Enigo, Mouse, Settings};
-                        match Enigo::new(&Settings::default()) {
-                            Ok(enigo) => enigo.location().unwrap_or((0, 0)),
-                            Err(_) => (0, 0),
-                        }
-                    }
-                };
-
-                let click = ClickPoint {
-                    x,
-                    y,
-                    monitor: 0,
-                    button: btn.to_string(),
-                };
-                println!("Click recorded: x={}, y={}, button={}", x, y, btn);
-
+            if let Some(recorded) = recorded {
                 if let Ok(mut clicks_lock) = clicks_arc.lock() {
-                    clicks_lock.push(click);
+                    clicks_lock.push(recorded);
                 }
             }
         };
@@ -1438,16 +1961,11 @@ async fn start_click_recording(state: tauri::State<'_, ClickRecordingState>) ->
             println!("Error listening for clicks: {:?}", error);
         }
     });
-
-    Ok(())
 }
 
-// Команда для остановки записи кликов
-#[tauri::command]
-fn stop_click_recording(state: tauri::State<'_, ClickRecordingState>) -> Result<Vec<ClickPoint>, String> {
-    println!("Stopping click recording...");
-
-    // Останавливаем запись
+// Останавливает запись и возвращает накопленные события. Общая логика для
+// stop_click_recording и hotkey-тумблера из setup().
+fn end_click_recording(state: &ClickRecordingState) -> Vec<RecordedEvent> {
     STOP_RECORDING.store(true, std::sync::atomic::Ordering::SeqCst);
 
     {
@@ -1455,157 +1973,503 @@ fn stop_click_recording(state: tauri::State<'_, ClickRecordingState>) -> Result<
         *is_recording = false;
     }
 
-    // Возвращаем записанные клики
     let clicks = state.clicks.lock().unwrap();
-    let result = clicks.clone();
+    clicks.clone()
+}
+
+// Команда для начала записи кликов
+#[tauri::command]
+async fn start_click_recording(state: tauri::State<'_, ClickRecordingState>) -> Result<(), String> {
+    println!("Starting click recording...");
+
+    // Очищаем предыдущую запись
+    {
+        let mut clicks = state.clicks.lock().unwrap();
+        clicks.clear();
+    }
+
+    // Устанавливаем флаг записи
+    {
+        let mut is_recording = state.is_recording.lock().unwrap();
+        *is_recording = true;
+    }
+
+    begin_click_recording(state.clicks.clone());
+
+    Ok(())
+}
+
+// Команда для остановки записи кликов
+#[tauri::command]
+fn stop_click_recording(state: tauri::State<'_, ClickRecordingState>) -> Result<Vec<RecordedEvent>, String> {
+    println!("Stopping click recording...");
+
+    let result = end_click_recording(&state);
 
     println!("Recording stopped, {} clicks captured", result.len());
     Ok(result)
 }
 
-// Команда для воспроизведения последовательности кликов
+// Переводит задержку между двумя событиями записи в реальный сон плеера:
+// применяет множитель скорости и не даёт одиночной длинной паузе (например,
+// отвлёкся во время записи) растянуть воспроизведение на минуты.
+fn scaled_delay_ms(delta_ms: u64, speed: f64, max_gap_ms: u64) -> u64 {
+    let clamped_delta = delta_ms.min(max_gap_ms);
+    ((clamped_delta as f64 / speed).round() as u64).max(1)
+}
+
+// Область экрана и частота кадров для записи воспроизведения на верификацию.
+// Монитор/прямоугольник задаются так же, как в capture_area_screenshot.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct ReplayCaptureConfig {
+    monitor: usize,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    fps: u8,
+}
+
+// Захватывает один кадр заданной области монитора в RGBA. Похоже на
+// crop_monitor_rgba, но читает экран напрямую, а не сохранённый скриншот,
+// потому что запись идёт параллельно с воспроизведением в реальном времени.
+fn capture_region_rgba(config: &ReplayCaptureConfig) -> Result<Vec<u8>, String> {
+    let screens = Screen::all().map_err(|e| format!("Failed to get screens: {}", e))?;
+    let screen = screens.get(config.monitor)
+        .ok_or_else(|| format!("Monitor {} not found", config.monitor))?;
+    let captured = screen.capture().map_err(|e| format!("Failed to capture screen: {}", e))?;
+
+    let full_width = captured.width();
+    let full_height = captured.height();
+    let rgba = captured.rgba();
+
+    if config.x + config.width > full_width || config.y + config.height > full_height {
+        return Err(format!(
+            "Capture region out of bounds: {}x{} at ({},{}) vs monitor {}x{}",
+            config.width, config.height, config.x, config.y, full_width, full_height
+        ));
+    }
+
+    let mut cropped = Vec::with_capacity((config.width * config.height * 4) as usize);
+    for row in config.y..(config.y + config.height) {
+        let start = ((row * full_width + config.x) * 4) as usize;
+        let end = start + (config.width * 4) as usize;
+        cropped.extend_from_slice(&rgba[start..end]);
+    }
+
+    Ok(cropped)
+}
+
+// Пишет один кадр на диск как PNG. Общий формат с capture_full_screenshot,
+// только сразу в файл вместо base64.
+fn write_frame_png(path: &std::path::Path, rgba: &[u8], width: u32, height: u32) -> Result<(), String> {
+    use png::Encoder;
+    use png::ColorType;
+    use std::io::BufWriter;
+
+    let file = fs::File::create(path).map_err(|e| format!("Failed to create frame file: {}", e))?;
+    let w = BufWriter::new(file);
+    let mut encoder = Encoder::new(w, width, height);
+    encoder.set_color(ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header().map_err(|e| format!("Failed to write PNG header: {}", e))?;
+    writer.write_image_data(rgba).map_err(|e| format!("Failed to write PNG data: {}", e))
+}
+
+// Записывает кадры области экрана с заданным fps, пока recording_flag не
+// станет false. Тот же start/stop флаг-паттерн, что и у записи кликов
+// (ClickRecordingState.is_recording), только локальный для одного воспроизведения.
+fn run_replay_capture(
+    config: ReplayCaptureConfig,
+    frames_dir: PathBuf,
+    recording_flag: std::sync::Arc<Mutex<bool>>,
+    window: Option<tauri::WebviewWindow>,
+) -> Result<usize, String> {
+    use tauri::Emitter;
+
+    let frame_interval = Duration::from_millis((1000 / config.fps.max(1) as u64).max(1));
+    let mut frame_index: usize = 0;
+
+    while *recording_flag.lock().unwrap() {
+        let frame_start = std::time::Instant::now();
+
+        let rgba = capture_region_rgba(&config)?;
+        let frame_path = frames_dir.join(format!("frame_{:06}.png", frame_index));
+        write_frame_png(&frame_path, &rgba, config.width, config.height)?;
+
+        frame_index += 1;
+        if let Some(window) = &window {
+            let _ = window.emit("replay-capture-progress", frame_index);
+        }
+
+        let elapsed = frame_start.elapsed();
+        if elapsed < frame_interval {
+            std::thread::sleep(frame_interval - elapsed);
+        }
+    }
+
+    Ok(frame_index)
+}
+
+// Кодирует записанные кадры в MP4, переиспользуя convert_to_mp4: сначала
+// склеиваем PNG-последовательность в промежуточный mjpeg-контейнер через
+// ffmpeg, а затем прогоняем его через тот же путь конвертации, что и
+// остальное видео в приложении.
+async fn encode_replay_capture(frames_dir: &std::path::Path, fps: u8) -> Result<String, String> {
+    use tokio::process::Command;
+
+    let intermediate_path = frames_dir.join("frames.avi");
+    let pattern = frames_dir.join("frame_%06d.png");
+
+    let output = Command::new("ffmpeg")
+        .args(&[
+            "-y",
+            "-framerate", &fps.max(1).to_string(),
+            "-i", &pattern.to_string_lossy(),
+            "-c:v", "mjpeg",
+            "-q:v", "3",
+            &intermediate_path.to_string_lossy(),
+        ])
+        .output()
+        .await
+        .map_err(|e| format!("Ошибка запуска FFmpeg: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Ошибка сборки кадров в видео: {}", stderr));
+    }
+
+    let encoded_path = convert_to_mp4(intermediate_path.to_string_lossy().to_string()).await?;
+
+    // convert_to_mp4 writes its output next to the input (inside frames_dir),
+    // but the caller removes frames_dir right after encoding — move the final
+    // file out first so the path we return isn't deleted out from under it.
+    let final_path = frames_dir.with_extension("mp4");
+    tokio::fs::rename(&encoded_path, &final_path)
+        .await
+        .map_err(|e| format!("Failed to move encoded capture out of temp dir: {}", e))?;
+
+    Ok(final_path.to_string_lossy().to_string())
+}
+
+// Команда для воспроизведения последовательности кликов с реальными
+// интервалами из записи (а не фиксированной паузой между каждым событием).
+// Бэкенд ввода (xdotool/ydotool/enigo) выбирается один раз под конкретную
+// сессию, чтобы воспроизведение работало и на X11, и на Wayland. Координаты
+// мыши зажимаются в границы своего монитора, а курсор после воспроизведения
+// (или после остановки через stop_replay/STOP_REPLAY) возвращается на
+// исходную позицию. Если передан capture, параллельно пишется запись области
+// экрана на верификацию, которая кодируется в MP4 и возвращается по завершении.
 #[tauri::command]
-async fn play_click_sequence(clicks: Vec<ClickPoint>, interval_ms: u64, repeat_count: u32) -> Result<(), String> {
-    println!("Playing {} clicks with {}ms interval, {} repeat(s)...", clicks.len(), interval_ms, repeat_count);
+async fn play_click_sequence(
+    app_handle: tauri::AppHandle,
+    clicks: Vec<RecordedEvent>,
+    speed: f64,
+    repeat_count: u32,
+    max_gap_ms: u64,
+    capture: Option<ReplayCaptureConfig>,
+) -> Result<Option<String>, String> {
+    let backend = input_backend::detect_input_backend();
+    println!(
+        "Playing {} event(s) at {}x speed, {} repeat(s), max gap {}ms, backend={}, capture={}...",
+        clicks.len(), speed, repeat_count, max_gap_ms, backend.label(), capture.is_some()
+    );
+
+    // Защита от деления на ноль/отрицательную скорость
+    let speed = if speed > 0.0 { speed } else { 1.0 };
+
+    STOP_REPLAY.store(false, std::sync::atomic::Ordering::SeqCst);
+
+    // Границы мониторов, чтобы не увести курсор за пределы экрана
+    let screens = Screen::all().map_err(|e| format!("Failed to get screens: {}", e))?;
+    let bounds: Vec<(i32, i32, i32, i32)> = screens
+        .iter()
+        .map(|screen| {
+            let info = &screen.display_info;
+            (info.x, info.y, info.x + info.width as i32, info.y + info.height as i32)
+        })
+        .collect();
+
+    let clamp_to_monitor = move |monitor: usize, x: i32, y: i32| -> (i32, i32) {
+        match bounds.get(monitor) {
+            Some(&(min_x, min_y, max_x, max_y)) if max_x > min_x && max_y > min_y => {
+                (x.clamp(min_x, max_x - 1), y.clamp(min_y, max_y - 1))
+            }
+            _ => (x, y),
+        }
+    };
+
+    // Запоминаем позицию курсора, чтобы восстановить её после воспроизведения
+    let original_position = input_backend::get_cursor_position(backend);
+
+    // Если запрошена верификационная запись — запускаем отдельный поток
+    // захвата кадров, который работает, пока воспроизведение не завершится.
+    let capture_session = if let Some(config) = capture.clone() {
+        let frames_dir = std::env::temp_dir().join(format!("bro-replay-capture-{}", std::process::id()));
+        fs::create_dir_all(&frames_dir).map_err(|e| format!("Failed to create capture dir: {}", e))?;
+
+        let recording_flag = std::sync::Arc::new(Mutex::new(true));
+        let recording_flag_task = recording_flag.clone();
+        let frames_dir_task = frames_dir.clone();
+        let window = app_handle.get_webview_window("main");
+
+        let task = tokio::task::spawn_blocking(move || {
+            run_replay_capture(config, frames_dir_task, recording_flag_task, window)
+        });
+
+        Some((recording_flag, frames_dir, task))
+    } else {
+        None
+    };
+
+    let playback_result = tokio::task::spawn_blocking(move || -> Result<(), String> {
+        'repeats: for repeat in 0..repeat_count {
+            if STOP_REPLAY.load(std::sync::atomic::Ordering::SeqCst) {
+                println!("Replay stopped");
+                break;
+            }
 
-    tokio::task::spawn_blocking(move || -> Result<(), String> {
-        for repeat in 0..repeat_count {
             if repeat_count > 1 {
                 println!("=== Repeat {}/{} ===", repeat + 1, repeat_count);
             }
 
-            // Используем xdotool на Linux для более точных кликов
-            #[cfg(target_os = "linux")]
-            {
-                use std::process::Command;
-
-                // Получаем текущую позицию курсора
-                let mut current_x = 0i32;
-                let mut current_y = 0i32;
-
-                let output = Command::new("xdotool")
-                    .args(&["getmouselocation", "--shell"])
-                    .output();
-
-                if let Ok(output) = output {
-                    let stdout = String::from_utf8_lossy(&output.stdout);
-                    for line in stdout.lines() {
-                        if line.starts_with("X=") {
-                            current_x = line[2..].parse().unwrap_or(0);
-                        } else if line.starts_with("Y=") {
-                            current_y = line[2..].parse().unwrap_or(0);
+            let mut previous_timestamp_ms = 0u64;
+            let (mut current_x, mut current_y) = input_backend::get_cursor_position(backend);
+
+            for (i, event) in clicks.iter().enumerate() {
+                if STOP_REPLAY.load(std::sync::atomic::Ordering::SeqCst) {
+                    println!("Replay stopped");
+                    break 'repeats;
+                }
+
+                let delta_ms = event.timestamp_ms().saturating_sub(previous_timestamp_ms);
+                previous_timestamp_ms = event.timestamp_ms();
+                std::thread::sleep(Duration::from_millis(scaled_delay_ms(delta_ms, speed, max_gap_ms)));
+
+                match event {
+                    RecordedEvent::Mouse { x, y, monitor, button, .. } => {
+                        let (x, y) = clamp_to_monitor(*monitor, *x, *y);
+
+                        // Плавное перемещение курсора (медленно для хорошей видимости)
+                        let steps = 20;
+                        let dx = (x - current_x) as f64 / steps as f64;
+                        let dy = (y - current_y) as f64 / steps as f64;
+
+                        println!("Moving cursor from ({}, {}) to ({}, {})", current_x, current_y, x, y);
+
+                        for step in 1..=steps {
+                            let intermediate_x = current_x + (dx * step as f64) as i32;
+                            let intermediate_y = current_y + (dy * step as f64) as i32;
+                            let _ = input_backend::move_mouse_abs(backend, intermediate_x, intermediate_y);
+                            std::thread::sleep(Duration::from_millis(10));
                         }
+
+                        // Финальная позиция
+                        input_backend::move_mouse_abs(backend, x, y)?;
+                        std::thread::sleep(Duration::from_millis(50));
+
+                        input_backend::click(backend, button)?;
+                        println!("Click {} ({}) at ({}, {})", i + 1, button, x, y);
+
+                        current_x = x;
+                        current_y = y;
+                    }
+                    RecordedEvent::Scroll { direction, .. } => {
+                        input_backend::scroll(backend, direction)?;
+                        println!("Scroll {} ({})", i + 1, direction);
+                    }
+                    RecordedEvent::Key { key, direction, .. } => {
+                        input_backend::key_event(backend, key, direction)?;
+                        println!("Key {} {} ({})", direction, key, i + 1);
                     }
                 }
+            }
+        }
 
-                for (i, click) in clicks.iter().enumerate() {
-                    // Плавное перемещение курсора (медленно для хорошей видимости)
-                    let steps = 100; // количество шагов для плавности
-                    let dx = (click.x - current_x) as f64 / steps as f64;
-                    let dy = (click.y - current_y) as f64 / steps as f64;
+        println!("Click sequence completed ({} repeats)", repeat_count);
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?;
 
-                    println!("Moving cursor from ({}, {}) to ({}, {})", current_x, current_y, click.x, click.y);
+    // Возвращаем курсор на исходную позицию и при нормальном завершении, и при прерывании
+    let _ = input_backend::move_mouse_abs(backend, original_position.0, original_position.1);
 
-                    for step in 1..=steps {
-                        let intermediate_x = current_x + (dx * step as f64) as i32;
-                        let intermediate_y = current_y + (dy * step as f64) as i32;
+    // Останавливаем поток захвата и при ошибке воспроизведения
+    if playback_result.is_err() {
+        if let Some((recording_flag, _, _)) = &capture_session {
+            *recording_flag.lock().unwrap() = false;
+        }
+    }
 
-                        let _ = Command::new("xdotool")
-                            .args(&["mousemove", &intermediate_x.to_string(), &intermediate_y.to_string()])
-                            .status();
+    playback_result?;
 
-                        std::thread::sleep(Duration::from_millis(10));
-                    }
+    let output_path = if let Some((recording_flag, frames_dir, capture_task)) = capture_session {
+        *recording_flag.lock().unwrap() = false;
 
-                    // Финальная позиция
-                    let _ = Command::new("xdotool")
-                        .args(&["mousemove", &click.x.to_string(), &click.y.to_string()])
-                        .status();
+        let frame_count = capture_task
+            .await
+            .map_err(|e| format!("Capture task join error: {}", e))??;
 
-                    std::thread::sleep(Duration::from_millis(50));
+        println!("Captured {} frame(s), encoding replay capture...", frame_count);
+        if let Some(window) = app_handle.get_webview_window("main") {
+            use tauri::Emitter;
+            let _ = window.emit("replay-capture-encoding", frame_count);
+        }
 
-                    // Кликаем нужной кнопкой
-                    let button_num = if click.button == "right" { "3" } else { "1" };
-                    let result = Command::new("xdotool")
-                        .arg("click")
-                        .arg(button_num)
-                        .status();
+        let fps = capture.map(|c| c.fps).unwrap_or(1);
+        let final_path = encode_replay_capture(&frames_dir, fps).await;
 
-                    if let Err(e) = result {
-                        return Err(format!("Failed to click: {}", e));
-                    }
+        let _ = fs::remove_dir_all(&frames_dir);
 
-                    println!("Click {} ({}) at ({}, {})", i + 1, click.button, click.x, click.y);
+        let final_path = final_path?;
+        if let Some(window) = app_handle.get_webview_window("main") {
+            use tauri::Emitter;
+            let _ = window.emit("replay-capture-done", &final_path);
+        }
 
-                    current_x = click.x;
-                    current_y = click.y;
+        Some(final_path)
+    } else {
+        None
+    };
 
-                    // Задержка между кликами
-                    if i < clicks.len() - 1 {
-                        std::thread::sleep(Duration::from_millis(interval_ms));
-                    }
-                }
-            }
+    Ok(output_path)
+}
 
-            #[cfg(not(target_os = "linux"))]
-            {
-                use enigo::{Enigo, Mouse, Button, Coordinate, Settings};
+// Команда для остановки воспроизведения записи
+#[tauri::command]
+fn stop_replay() -> Result<(), String> {
+    println!("Replay stop requested");
+    STOP_REPLAY.store(true, std::sync::atomic::Ordering::SeqCst);
+    Ok(())
+}
 
-                let mut enigo = Enigo::new(&Settings::default())
-                    .map_err(|e| format!("Failed to create Enigo: {:?}", e))?;
+// Метаданные макроса для списка в UI, без самих событий
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct MacroMeta {
+    name: String,
+    created_at_unix: u64,
+    event_count: usize,
+    screen_width: u32,
+    screen_height: u32,
+}
 
-                // Получаем текущую позицию
-                let (mut current_x, mut current_y) = enigo.location().unwrap_or((0, 0));
+// Файл одного именованного макроса на диске: метаданные + сами события
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct MacroFile {
+    meta: MacroMeta,
+    events: Vec<RecordedEvent>,
+}
 
-                for (i, click) in clicks.iter().enumerate() {
-                    // Плавное перемещение курсора
-                    let steps = 20;
-                    let dx = (click.x - current_x) as f64 / steps as f64;
-                    let dy = (click.y - current_y) as f64 / steps as f64;
+// Каталог с библиотекой макросов внутри конфига приложения, по одному файлу
+// на макрос.
+fn get_macros_dir() -> Option<PathBuf> {
+    if let Some(proj_dirs) = directories::ProjectDirs::from("com", "bro", "bro") {
+        let macros_dir = proj_dirs.config_dir().join("macros");
+        fs::create_dir_all(&macros_dir).ok()?;
+        Some(macros_dir)
+    } else {
+        None
+    }
+}
 
-                    for step in 1..=steps {
-                        let intermediate_x = current_x + (dx * step as f64) as i32;
-                        let intermediate_y = current_y + (dy * step as f64) as i32;
+// Имя макроса приходит от пользователя и становится именем файла, поэтому
+// вырезаем всё, кроме букв/цифр/пробелов/подчёркиваний/дефисов, чтобы нельзя
+// было выйти за пределы каталога макросов.
+fn sanitize_macro_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric() || *c == '_' || *c == '-' || *c == ' ')
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
 
-                        let _ = enigo.move_mouse(intermediate_x, intermediate_y, Coordinate::Abs);
-                        std::thread::sleep(Duration::from_millis(5));
-                    }
+fn macro_file_path(name: &str) -> Result<PathBuf, String> {
+    let macros_dir = get_macros_dir().ok_or_else(|| "Failed to resolve macros directory".to_string())?;
+    let sanitized = sanitize_macro_name(name);
+    if sanitized.is_empty() {
+        return Err("Macro name must not be empty".to_string());
+    }
+    Ok(macros_dir.join(format!("{}.json", sanitized)))
+}
 
-                    // Финальная позиция
-                    enigo.move_mouse(click.x, click.y, Coordinate::Abs)
-                        .map_err(|e| format!("Failed to move mouse: {:?}", e))?;
+// Команда для сохранения именованного макроса в отдельный файл с метаданными
+#[tauri::command]
+fn save_click_macro(name: String, events: Vec<RecordedEvent>) -> Result<(), String> {
+    let path = macro_file_path(&name)?;
 
-                    std::thread::sleep(Duration::from_millis(50));
+    let screens = Screen::all().map_err(|e| format!("Failed to get screens: {}", e))?;
+    let (screen_width, screen_height) = screens
+        .first()
+        .map(|screen| (screen.display_info.width, screen.display_info.height))
+        .unwrap_or((0, 0));
+
+    let created_at_unix = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let macro_file = MacroFile {
+        meta: MacroMeta {
+            name: name.clone(),
+            created_at_unix,
+            event_count: events.len(),
+            screen_width,
+            screen_height,
+        },
+        events,
+    };
 
-                    // Кликаем нужной кнопкой
-                    let btn = if click.button == "right" { Button::Right } else { Button::Left };
-                    enigo.button(btn, enigo::Direction::Click)
-                        .map_err(|e| format!("Failed to click: {:?}", e))?;
+    let content = serde_json::to_string_pretty(&macro_file)
+        .map_err(|e| format!("Failed to serialize macro: {}", e))?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write macro file: {}", e))?;
 
-                    println!("Click {} ({}) at ({}, {})", i + 1, click.button, click.x, click.y);
+    println!("Macro '{}' saved to {:?}", name, path);
+    Ok(())
+}
 
-                    current_x = click.x;
-                    current_y = click.y;
+// Команда для получения списка сохранённых макросов (только метаданные)
+#[tauri::command]
+fn list_click_macros() -> Result<Vec<MacroMeta>, String> {
+    let macros_dir = get_macros_dir().ok_or_else(|| "Failed to resolve macros directory".to_string())?;
 
-                    // Задержка между кликами
-                    if i < clicks.len() - 1 {
-                        std::thread::sleep(Duration::from_millis(interval_ms));
-                    }
-                }
-            }
+    let entries = fs::read_dir(&macros_dir).map_err(|e| format!("Failed to read macros directory: {}", e))?;
 
-            // Задержка между повторениями
-            if repeat < repeat_count - 1 {
-                std::thread::sleep(Duration::from_millis(interval_ms));
+    let mut result = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+
+        if let Ok(content) = fs::read_to_string(&path) {
+            if let Ok(macro_file) = serde_json::from_str::<MacroFile>(&content) {
+                result.push(macro_file.meta);
             }
         }
+    }
 
-        println!("Click sequence completed ({} repeats)", repeat_count);
-        Ok(())
-    })
-    .await
-    .map_err(|e| format!("Task join error: {}", e))??;
+    result.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(result)
+}
 
+// Команда для загрузки событий сохранённого макроса по имени
+#[tauri::command]
+fn load_click_macro(name: String) -> Result<Vec<RecordedEvent>, String> {
+    let path = macro_file_path(&name)?;
+    let content = fs::read_to_string(&path).map_err(|_| format!("Macro '{}' not found", name))?;
+    let macro_file: MacroFile = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse macro '{}': {}", name, e))?;
+    Ok(macro_file.events)
+}
+
+// Команда для удаления сохранённого макроса
+#[tauri::command]
+fn delete_click_macro(name: String) -> Result<(), String> {
+    let path = macro_file_path(&name)?;
+    fs::remove_file(&path).map_err(|e| format!("Failed to delete macro '{}': {}", name, e))?;
+    println!("Macro '{}' deleted", name);
     Ok(())
 }
 
@@ -1654,6 +2518,13 @@ pub fn run() {
             screen_x: Mutex::new(0),
             screen_y: Mutex::new(0),
         })
+        .manage(ConversionState {
+            child_pid: Mutex::new(None),
+        })
+        .manage(StreamServerState {
+            shutdown: Mutex::new(None),
+            handle: Mutex::new(None),
+        })
         .setup(|app| {
             // F12, F5, F11 теперь обрабатываются на фронтенде, а не глобально
 
@@ -1695,6 +2566,70 @@ pub fn run() {
                 Err(e) => eprintln!("Failed to register Ctrl+PrintScreen shortcut: {}", e),
             }
 
+            // Регистрируем горячую клавишу переключения записи кликов, если она сохранена
+            if let Some(record_toggle_hotkey) = saved_state.record_toggle_hotkey.clone() {
+                println!("Registering click-recording toggle hotkey: {}", record_toggle_hotkey);
+
+                match app.global_shortcut().on_shortcut(record_toggle_hotkey.as_str(), move |app, _shortcut, event| {
+                    use tauri::Emitter;
+
+                    if event.state == ShortcutState::Pressed {
+                        let click_state = app.state::<ClickRecordingState>();
+                        let was_recording = *click_state.is_recording.lock().unwrap();
+
+                        if was_recording {
+                            let clicks = end_click_recording(&click_state);
+                            println!("Click recording stopped via hotkey, {} event(s) captured", clicks.len());
+                        } else {
+                            { click_state.clicks.lock().unwrap().clear(); }
+                            { *click_state.is_recording.lock().unwrap() = true; }
+                            begin_click_recording(click_state.clicks.clone());
+                            println!("Click recording started via hotkey");
+                        }
+
+                        if let Some(window) = app.get_webview_window("main") {
+                            let _ = window.emit("click-recording-toggled", !was_recording);
+                        }
+                    }
+                }) {
+                    Ok(_) => println!("Click-recording toggle hotkey registered successfully"),
+                    Err(e) => eprintln!("Failed to register click-recording toggle hotkey: {}", e),
+                }
+            }
+
+            // Регистрируем горячую клавишу воспроизведения записи, если она сохранена
+            if let Some(replay_hotkey) = saved_state.replay_hotkey.clone() {
+                println!("Registering click-replay hotkey: {}", replay_hotkey);
+
+                match app.global_shortcut().on_shortcut(replay_hotkey.as_str(), move |app, _shortcut, event| {
+                    use tauri::Emitter;
+
+                    if event.state == ShortcutState::Pressed {
+                        let click_state = app.state::<ClickRecordingState>();
+                        let clicks = click_state.clicks.lock().unwrap().clone();
+
+                        if clicks.is_empty() {
+                            println!("Replay hotkey pressed but no recording is available");
+                            return;
+                        }
+
+                        if let Some(window) = app.get_webview_window("main") {
+                            let _ = window.emit("click-replay-started", clicks.len());
+                        }
+
+                        let app_handle = app.clone();
+                        tauri::async_runtime::spawn(async move {
+                            if let Err(e) = play_click_sequence(app_handle, clicks, 1.0, 1, 2000, None).await {
+                                eprintln!("Hotkey-triggered replay failed: {}", e);
+                            }
+                        });
+                    }
+                }) {
+                    Ok(_) => println!("Click-replay hotkey registered successfully"),
+                    Err(e) => eprintln!("Failed to register click-replay hotkey: {}", e),
+                }
+            }
+
             // Восстанавливаем состояние DevTools
             let saved_state = load_state();
             println!("Loaded state: devtools_open = {}", saved_state.devtools_open);
@@ -1784,11 +2719,14 @@ pub fn run() {
             open_area_selector,
             get_stored_screenshot,
             capture_area_screenshot,
+            copy_area_to_clipboard,
             close_all_area_selectors,
             handle_area_selection,
             open_translation_popup,
             get_popup_screenshot,
             get_popup_screen_position,
+            set_popup_visible_on_all_workspaces,
+            set_popup_always_on_top,
             close_translation_popup,
             solve_and_click,
             move_translation_popup,
@@ -1797,6 +2735,10 @@ pub fn run() {
             set_window_size,
             save_translation_hotkey,
             get_translation_hotkey,
+            save_record_toggle_hotkey,
+            get_record_toggle_hotkey,
+            save_replay_hotkey,
+            get_replay_hotkey,
             save_last_route,
             get_last_route,
             save_openai_api_key,
@@ -1806,17 +2748,31 @@ pub fn run() {
             save_auto_open_links,
             get_auto_open_links,
             open_url_in_browser,
-            send_to_chatgpt,
-            send_to_claude,
+            send_to_provider,
+            stream_to_provider,
+            save_vision_model,
+            get_vision_model,
+            save_vision_max_tokens,
+            get_vision_max_tokens,
             type_text,
             toggle_devtools,
             open_terminal,
             convert_to_mp4,
+            convert_video,
+            cancel_conversion,
             get_jetbrains_projects,
             open_jetbrains_project,
             start_click_recording,
             stop_click_recording,
-            play_click_sequence
+            play_click_sequence,
+            stop_replay,
+            save_click_macro,
+            list_click_macros,
+            load_click_macro,
+            delete_click_macro,
+            get_input_backend_status,
+            start_screen_stream,
+            stop_screen_stream
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,151 @@
+//! GStreamer-backed encoder used as an alternative to the per-frame JPEG
+//! path in `websocket_stream`. Frames are pushed in as raw RGBA and the
+//! pipeline emits an inter-frame-compressed H.264/VP8 byte stream, which
+//! costs far less bandwidth than an independent JPEG keyframe per tick.
+
+use gst::prelude::*;
+use gstreamer as gst;
+use gstreamer_app as gst_app;
+use gstreamer_video as gst_video;
+
+/// Video codec used to encode captured frames for streaming.
+#[derive(Clone, Copy, Debug)]
+pub enum VideoCodec {
+    H264 { bitrate_kbps: u32 },
+    Vp8 { bitrate_kbps: u32 },
+}
+
+impl VideoCodec {
+    fn encoder_element(&self) -> Result<gst::Element, String> {
+        match self {
+            VideoCodec::H264 { bitrate_kbps } => gst::ElementFactory::make("x264enc")
+                .property("bitrate", *bitrate_kbps)
+                .property_from_str("tune", "zerolatency")
+                .property_from_str("speed-preset", "ultrafast")
+                .property("key-int-max", 120u32)
+                .build()
+                .map_err(|e| format!("Failed to create x264enc: {}", e)),
+            VideoCodec::Vp8 { bitrate_kbps } => gst::ElementFactory::make("vp8enc")
+                .property("target-bitrate", (*bitrate_kbps as i32) * 1000)
+                .property("deadline", 1i64)
+                .build()
+                .map_err(|e| format!("Failed to create vp8enc: {}", e)),
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            VideoCodec::H264 { .. } => "video/mp4; codecs=\"avc1.42E01E\"",
+            VideoCodec::Vp8 { .. } => "video/webm; codecs=\"vp8\"",
+        }
+    }
+}
+
+/// Wraps an `appsrc -> videoconvert -> <encoder> -> appsink` pipeline.
+/// Feed captured RGBA frames in with `push_frame` and drain encoded chunks
+/// with `try_pull_encoded`.
+pub struct GstEncoder {
+    pipeline: gst::Pipeline,
+    appsrc: gst_app::AppSrc,
+    appsink: gst_app::AppSink,
+    width: u32,
+    height: u32,
+    frame_duration: gst::ClockTime,
+    frame_index: u64,
+}
+
+impl GstEncoder {
+    pub fn new(codec: VideoCodec, width: u32, height: u32, fps: u32) -> Result<Self, String> {
+        gst::init().map_err(|e| format!("Failed to init GStreamer: {}", e))?;
+
+        let pipeline = gst::Pipeline::new();
+
+        let video_info = gst_video::VideoInfo::builder(gst_video::VideoFormat::Rgba, width, height)
+            .fps(gst::Fraction::new(fps as i32, 1))
+            .build()
+            .map_err(|e| format!("Failed to build video info: {}", e))?;
+
+        let appsrc = gst_app::AppSrc::builder()
+            .caps(&video_info.to_caps().map_err(|e| format!("Failed to build caps: {}", e))?)
+            .format(gst::Format::Time)
+            .is_live(true)
+            .build();
+
+        let videoconvert = gst::ElementFactory::make("videoconvert")
+            .build()
+            .map_err(|e| format!("Failed to create videoconvert: {}", e))?;
+
+        let encoder = codec.encoder_element()?;
+
+        let appsink = gst_app::AppSink::builder()
+            .sync(false)
+            .max_buffers(4)
+            .drop(true)
+            .build();
+
+        pipeline
+            .add_many([appsrc.upcast_ref(), &videoconvert, &encoder, appsink.upcast_ref()])
+            .map_err(|e| format!("Failed to add pipeline elements: {}", e))?;
+
+        gst::Element::link_many([appsrc.upcast_ref(), &videoconvert, &encoder, appsink.upcast_ref()])
+            .map_err(|e| format!("Failed to link pipeline elements: {}", e))?;
+
+        pipeline
+            .set_state(gst::State::Playing)
+            .map_err(|e| format!("Failed to start pipeline: {}", e))?;
+
+        Ok(Self {
+            pipeline,
+            appsrc,
+            appsink,
+            width,
+            height,
+            frame_duration: gst::ClockTime::from_nseconds(1_000_000_000 / fps.max(1) as u64),
+            frame_index: 0,
+        })
+    }
+
+    /// Pushes one captured RGBA frame into the pipeline.
+    pub fn push_frame(&mut self, rgba: &[u8]) -> Result<(), String> {
+        let expected_len = (self.width * self.height * 4) as usize;
+        if rgba.len() != expected_len {
+            return Err(format!(
+                "Unexpected frame size: got {} bytes, expected {}",
+                rgba.len(),
+                expected_len
+            ));
+        }
+
+        let mut buffer = gst::Buffer::with_size(rgba.len()).map_err(|e| format!("Failed to allocate buffer: {}", e))?;
+        {
+            let buffer_mut = buffer.get_mut().ok_or("Failed to get mutable buffer")?;
+            buffer_mut.set_pts(self.frame_duration * self.frame_index);
+            buffer_mut.set_duration(self.frame_duration);
+            let mut map = buffer_mut
+                .map_writable()
+                .map_err(|e| format!("Failed to map buffer: {}", e))?;
+            map.copy_from_slice(rgba);
+        }
+        self.frame_index += 1;
+
+        self.appsrc
+            .push_buffer(buffer)
+            .map_err(|e| format!("Failed to push frame into pipeline: {:?}", e))?;
+
+        Ok(())
+    }
+
+    /// Non-blocking pull of the next encoded chunk, if the pipeline has one ready.
+    pub fn try_pull_encoded(&self) -> Option<Vec<u8>> {
+        let sample = self.appsink.try_pull_sample(gst::ClockTime::ZERO)?;
+        let buffer = sample.buffer()?;
+        let map = buffer.map_readable().ok()?;
+        Some(map.as_slice().to_vec())
+    }
+}
+
+impl Drop for GstEncoder {
+    fn drop(&mut self) {
+        let _ = self.pipeline.set_state(gst::State::Null);
+    }
+}
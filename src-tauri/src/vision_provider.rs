@@ -0,0 +1,173 @@
+// Провайдеро-независимый vision-chat бэкенд. Раньше send_to_chatgpt и
+// send_to_claude были двумя почти одинаковыми функциями с захардкоженной
+// моделью и max_tokens; теперь обе формы запроса (обычная и SSE-стриминг)
+// собираются через VisionProvider, а модель/max_tokens приходят снаружи.
+
+use futures_util::StreamExt;
+
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VisionProvider {
+    OpenAi,
+    Anthropic,
+}
+
+impl VisionProvider {
+    fn endpoint(&self) -> &'static str {
+        match self {
+            VisionProvider::OpenAi => "https://api.openai.com/v1/chat/completions",
+            VisionProvider::Anthropic => "https://api.anthropic.com/v1/messages",
+        }
+    }
+
+    fn request_body(&self, model: &str, max_tokens: u32, image_base64: &str, prompt: &str, stream: bool) -> serde_json::Value {
+        match self {
+            VisionProvider::OpenAi => serde_json::json!({
+                "model": model,
+                "messages": [
+                    {
+                        "role": "user",
+                        "content": [
+                            { "type": "text", "text": prompt },
+                            { "type": "image_url", "image_url": { "url": format!("data:image/png;base64,{}", image_base64) } }
+                        ]
+                    }
+                ],
+                "max_tokens": max_tokens,
+                "stream": stream
+            }),
+            VisionProvider::Anthropic => serde_json::json!({
+                "model": model,
+                "max_tokens": max_tokens,
+                "stream": stream,
+                "messages": [
+                    {
+                        "role": "user",
+                        "content": [
+                            { "type": "image", "source": { "type": "base64", "media_type": "image/png", "data": image_base64 } },
+                            { "type": "text", "text": prompt }
+                        ]
+                    }
+                ]
+            }),
+        }
+    }
+
+    fn apply_auth(&self, request: reqwest::RequestBuilder, api_key: &str) -> reqwest::RequestBuilder {
+        match self {
+            VisionProvider::OpenAi => request.header("Authorization", format!("Bearer {}", api_key)),
+            VisionProvider::Anthropic => request
+                .header("x-api-key", api_key)
+                .header("anthropic-version", "2023-06-01"),
+        }
+    }
+
+    fn extract_content(&self, response_json: &serde_json::Value) -> Option<String> {
+        match self {
+            VisionProvider::OpenAi => response_json["choices"][0]["message"]["content"].as_str().map(String::from),
+            VisionProvider::Anthropic => response_json["content"][0]["text"].as_str().map(String::from),
+        }
+    }
+
+    // Достаёт кусочек текста из одного SSE "data: {...}" чанка, если он есть.
+    fn extract_delta(&self, chunk_json: &serde_json::Value) -> Option<String> {
+        match self {
+            VisionProvider::OpenAi => chunk_json["choices"][0]["delta"]["content"].as_str().map(String::from),
+            VisionProvider::Anthropic => {
+                if chunk_json["type"] == "content_block_delta" {
+                    chunk_json["delta"]["text"].as_str().map(String::from)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    // Блокирующий вызов: ждём полный ответ целиком, как раньше делали
+    // send_to_chatgpt/send_to_claude.
+    pub async fn send(
+        &self,
+        api_key: &str,
+        model: &str,
+        max_tokens: u32,
+        image_base64: &str,
+        prompt: &str,
+    ) -> Result<String, String> {
+        let client = reqwest::Client::new();
+        let body = self.request_body(model, max_tokens, image_base64, prompt, false);
+
+        let request = client
+            .post(self.endpoint())
+            .header("Content-Type", "application/json")
+            .json(&body);
+        let request = self.apply_auth(request, api_key);
+
+        let response = request.send().await.map_err(|e| format!("Failed to send request: {}", e))?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(format!("{:?} API error: {}", self, error_text));
+        }
+
+        let response_json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+        self.extract_content(&response_json).ok_or_else(|| "No content in response".to_string())
+    }
+
+    // Стрим-вариант: открываем SSE-эндпоинт провайдера и зовём on_token для
+    // каждого кусочка текста по мере его поступления.
+    pub async fn stream(
+        &self,
+        api_key: &str,
+        model: &str,
+        max_tokens: u32,
+        image_base64: &str,
+        prompt: &str,
+        mut on_token: impl FnMut(String),
+    ) -> Result<(), String> {
+        let client = reqwest::Client::new();
+        let body = self.request_body(model, max_tokens, image_base64, prompt, true);
+
+        let request = client
+            .post(self.endpoint())
+            .header("Content-Type", "application/json")
+            .json(&body);
+        let request = self.apply_auth(request, api_key);
+
+        let response = request.send().await.map_err(|e| format!("Failed to send request: {}", e))?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(format!("{:?} API error: {}", self, error_text));
+        }
+
+        let mut byte_stream = response.bytes_stream();
+        let mut buffer = String::new();
+
+        while let Some(chunk) = byte_stream.next().await {
+            let chunk = chunk.map_err(|e| format!("Stream read error: {}", e))?;
+            buffer.push_str(&String::from_utf8_lossy(&chunk));
+
+            while let Some(newline_pos) = buffer.find('\n') {
+                let line = buffer[..newline_pos].trim().to_string();
+                buffer.drain(..=newline_pos);
+
+                let Some(data) = line.strip_prefix("data:") else { continue };
+                let data = data.trim();
+                if data.is_empty() || data == "[DONE]" {
+                    continue;
+                }
+
+                let Ok(chunk_json) = serde_json::from_str::<serde_json::Value>(data) else { continue };
+                if let Some(token) = self.extract_delta(&chunk_json) {
+                    on_token(token);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
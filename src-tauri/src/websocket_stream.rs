@@ -1,51 +1,227 @@
-use std::net::{TcpListener, TcpStream};
+use std::pin::Pin;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::thread;
+use std::task::{Context, Poll};
 use std::time::Duration;
-use tungstenite::{accept, Message};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Notify;
+use tokio_tungstenite::accept_async;
+use tungstenite::Message;
 use screenshots::Screen;
-use std::io::{Cursor, Write};
+use std::io::Cursor;
 use image::ImageEncoder;
+use crate::video_pipeline::{GstEncoder, VideoCodec};
+use serde::Deserialize;
+
+/// Starting JPEG quality before the client's back-channel stats adjust it.
+const DEFAULT_JPEG_QUALITY: u8 = 60;
+
+/// Selects which transport carries the frame stream to the browser.
+#[derive(Clone, Copy, Debug)]
+pub enum Transport {
+    /// The original plain TCP WebSocket path (optionally wrapped in TLS).
+    Tcp,
+    /// QUIC/HTTP3 WebTransport, run alongside the TCP listener so the page
+    /// itself still loads over HTTP while frames travel over QUIC.
+    WebTransport {
+        port: u16,
+        delivery: crate::webtransport_stream::FrameDelivery,
+    },
+}
+
+/// Selects how captured frames are encoded before being sent to the client.
+#[derive(Clone, Copy, Debug)]
+pub enum StreamCodec {
+    /// One independent JPEG keyframe per tick (the original, simple path).
+    Jpeg,
+    /// Inter-frame-compressed video via the GStreamer pipeline.
+    Video(VideoCodec),
+}
+
+/// Certificate/key material used to serve the stream over `wss://` instead of `ws://`.
+#[derive(Clone)]
+pub enum TlsConfig {
+    /// PEM-encoded cert chain and private key read from disk.
+    Files { cert_path: String, key_path: String },
+    /// DER-encoded cert chain and private key already held in memory.
+    Der { cert_chain: Vec<Vec<u8>>, private_key: Vec<u8> },
+}
+
+fn build_tls_acceptor(config: &TlsConfig) -> Result<tokio_rustls::TlsAcceptor, String> {
+    let (cert_chain, private_key): (Vec<rustls::pki_types::CertificateDer<'static>>, rustls::pki_types::PrivateKeyDer<'static>) = match config {
+        TlsConfig::Files { cert_path, key_path } => {
+            let cert_file = std::fs::File::open(cert_path)
+                .map_err(|e| format!("Failed to open cert file {}: {}", cert_path, e))?;
+            let mut cert_reader = std::io::BufReader::new(cert_file);
+            let cert_chain = rustls_pemfile::certs(&mut cert_reader)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("Failed to parse cert chain: {}", e))?;
+
+            let key_file = std::fs::File::open(key_path)
+                .map_err(|e| format!("Failed to open key file {}: {}", key_path, e))?;
+            let mut key_reader = std::io::BufReader::new(key_file);
+            let private_key = rustls_pemfile::private_key(&mut key_reader)
+                .map_err(|e| format!("Failed to parse private key: {}", e))?
+                .ok_or_else(|| format!("No private key found in {}", key_path))?;
+
+            (cert_chain, private_key)
+        }
+        TlsConfig::Der { cert_chain, private_key } => {
+            let cert_chain = cert_chain
+                .iter()
+                .cloned()
+                .map(rustls::pki_types::CertificateDer::from)
+                .collect();
+            let private_key = rustls::pki_types::PrivateKeyDer::try_from(private_key.clone())
+                .map_err(|e| format!("Invalid private key: {}", e))?;
+            (cert_chain, private_key)
+        }
+    };
+
+    let server_config = rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(cert_chain, private_key)
+        .map_err(|e| format!("Failed to build TLS config: {}", e))?;
+
+    Ok(tokio_rustls::TlsAcceptor::from(Arc::new(server_config)))
+}
+
+/// Either a plain TCP stream or one wrapped in a TLS session, so the rest of
+/// the server can treat `ws://` and `wss://` connections identically.
+enum ConnStream {
+    Plain(TcpStream),
+    Tls(Box<tokio_rustls::server::TlsStream<TcpStream>>),
+}
+
+impl AsyncRead for ConnStream {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            ConnStream::Plain(s) => Pin::new(s).poll_read(cx, buf),
+            ConnStream::Tls(s) => Pin::new(s.as_mut()).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for ConnStream {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        match self.get_mut() {
+            ConnStream::Plain(s) => Pin::new(s).poll_write(cx, buf),
+            ConnStream::Tls(s) => Pin::new(s.as_mut()).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            ConnStream::Plain(s) => Pin::new(s).poll_flush(cx),
+            ConnStream::Tls(s) => Pin::new(s.as_mut()).poll_flush(cx),
+        }
+    }
 
-pub fn start_websocket_server(
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            ConnStream::Plain(s) => Pin::new(s).poll_shutdown(cx),
+            ConnStream::Tls(s) => Pin::new(s.as_mut()).poll_shutdown(cx),
+        }
+    }
+}
+
+/// Replays a buffered prefix (used to sniff the request) before falling
+/// through to the underlying stream, so the sniff read doesn't get lost.
+struct PrefixedStream<S> {
+    prefix: Vec<u8>,
+    pos: usize,
+    inner: S,
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for PrefixedStream<S> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+        if self.pos < self.prefix.len() {
+            let remaining = &self.prefix[self.pos..];
+            let n = remaining.len().min(buf.remaining());
+            buf.put_slice(&remaining[..n]);
+            self.pos += n;
+            Poll::Ready(Ok(()))
+        } else {
+            Pin::new(&mut self.inner).poll_read(cx, buf)
+        }
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for PrefixedStream<S> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// Starts the hybrid HTTP/WebSocket server on the tokio runtime. The returned
+/// handle resolves once `shutdown` has been notified and the listener has
+/// stopped accepting new connections.
+pub async fn start_websocket_server(
     port: u16,
     screen_index: usize,
-    stop_signal: Arc<AtomicBool>
-) -> Result<std::thread::JoinHandle<()>, String> {
+    shutdown: Arc<Notify>,
+    tls_config: Option<TlsConfig>,
+    keepalive_interval: Duration,
+    codec: StreamCodec,
+    transport: Transport,
+    remote_input_enabled: bool,
+) -> Result<tokio::task::JoinHandle<()>, String> {
+    if let Transport::WebTransport { port: wt_port, delivery } = transport {
+        let wt_tls = tls_config.clone().ok_or("WebTransport requires a TLS config (HTTP/3 mandates TLS)")?;
+        let wt_shutdown = shutdown.clone();
+        let wt_config = crate::webtransport_stream::WebTransportConfig {
+            port: wt_port,
+            tls: wt_tls,
+            delivery,
+        };
+        crate::webtransport_stream::start_webtransport_server(wt_config, wt_shutdown, move || {
+            capture_frame_jpeg_sync(screen_index, DEFAULT_JPEG_QUALITY).ok()
+        })
+        .await?;
+    }
+
     let addr = format!("0.0.0.0:{}", port);
     println!("Starting hybrid HTTP/WebSocket server on {}", addr);
 
+    let tls_acceptor = tls_config.as_ref().map(build_tls_acceptor).transpose()?;
+    let use_tls = tls_acceptor.is_some();
+
     let listener = TcpListener::bind(&addr)
+        .await
         .map_err(|e| format!("Failed to bind server: {}", e))?;
 
-    listener.set_nonblocking(true)
-        .map_err(|e| format!("Failed to set nonblocking: {}", e))?;
-
-    println!("Hybrid server listening on {}", addr);
+    println!("Hybrid server listening on {} ({})", addr, if use_tls { "wss" } else { "ws" });
 
-    let handle = thread::spawn(move || {
-        for stream in listener.incoming() {
-            if stop_signal.load(Ordering::SeqCst) {
-                println!("Stop signal received, shutting down server");
-                break;
-            }
-
-            match stream {
-                Ok(stream) => {
-                    let stop_signal_clone = stop_signal.clone();
-                    thread::spawn(move || {
-                        if let Err(e) = handle_connection(stream, stop_signal_clone, screen_index) {
-                            eprintln!("Client error: {}", e);
-                        }
-                    });
+    let handle = tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = shutdown.notified() => {
+                    println!("Stop signal received, shutting down server");
+                    break;
                 }
-                Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                    thread::sleep(Duration::from_millis(100));
-                    continue;
-                }
-                Err(e) => {
-                    eprintln!("Connection error: {}", e);
+                accepted = listener.accept() => {
+                    match accepted {
+                        Ok((stream, _addr)) => {
+                            let shutdown_clone = shutdown.clone();
+                            let tls_acceptor = tls_acceptor.clone();
+                            tokio::spawn(async move {
+                                if let Err(e) = handle_connection(stream, shutdown_clone, screen_index, tls_acceptor, use_tls, keepalive_interval, codec, transport, remote_input_enabled).await {
+                                    eprintln!("Client error: {}", e);
+                                }
+                            });
+                        }
+                        Err(e) => {
+                            eprintln!("Connection error: {}", e);
+                        }
+                    }
                 }
             }
         }
@@ -55,25 +231,33 @@ pub fn start_websocket_server(
     Ok(handle)
 }
 
-fn handle_connection(
+async fn handle_connection(
     stream: TcpStream,
-    stop_signal: Arc<AtomicBool>,
-    screen_index: usize
+    shutdown: Arc<Notify>,
+    screen_index: usize,
+    tls_acceptor: Option<tokio_rustls::TlsAcceptor>,
+    use_tls: bool,
+    keepalive_interval: Duration,
+    codec: StreamCodec,
+    transport: Transport,
+    remote_input_enabled: bool,
 ) -> Result<(), String> {
-    // Читаем начало запроса чтобы определить тип (HTTP или WebSocket)
-    let mut buffer = [0u8; 8192];
-
-    stream.set_read_timeout(Some(Duration::from_secs(5))).ok();
-
-    // Читаем данные с помощью peek (не удаляем из буфера)
-    let n = match stream.peek(&mut buffer) {
-        Ok(n) => n,
-        Err(e) => {
-            eprintln!("Failed to peek stream: {}", e);
-            return Err(e.to_string());
+    let mut conn_stream = match tls_acceptor {
+        Some(acceptor) => {
+            let tls_stream = acceptor.accept(stream)
+                .await
+                .map_err(|e| format!("TLS handshake failed: {}", e))?;
+            ConnStream::Tls(Box::new(tls_stream))
         }
+        None => ConnStream::Plain(stream),
     };
 
+    // Читаем начало запроса чтобы определить тип (HTTP или WebSocket)
+    let mut buffer = [0u8; 8192];
+    let n = conn_stream.read(&mut buffer)
+        .await
+        .map_err(|e| format!("Failed to read stream: {}", e))?;
+
     if n == 0 {
         return Err("Empty request".to_string());
     }
@@ -87,130 +271,310 @@ fn handle_connection(
     let is_websocket = request.to_lowercase().contains("upgrade: websocket") ||
                       request.to_lowercase().contains("upgrade:websocket");
 
+    let prefixed = PrefixedStream { prefix: buffer[..n].to_vec(), pos: 0, inner: conn_stream };
+
     if is_websocket {
         println!("WebSocket connection detected");
-        handle_websocket(stream, stop_signal, screen_index)
+        handle_websocket(prefixed, shutdown, screen_index, keepalive_interval, codec, remote_input_enabled).await
     } else {
         println!("HTTP connection detected");
-        handle_http(stream)
+        handle_http(prefixed, use_tls, codec, transport, remote_input_enabled).await
     }
 }
 
-fn handle_http(mut stream: TcpStream) -> Result<(), String> {
-    let html = r#"<!DOCTYPE html>
-<html>
-<head>
-    <meta charset="UTF-8">
-    <title>WebSocket Screen Stream</title>
-    <style>
-        body {
-            margin: 0;
-            padding: 20px;
-            background: #1a1a1a;
-            color: #fff;
-            font-family: Arial, sans-serif;
-            display: flex;
-            flex-direction: column;
-            align-items: center;
-            justify-content: center;
-            min-height: 100vh;
-        }
-        h1 {
-            margin-bottom: 20px;
-        }
-        canvas {
-            max-width: 90vw;
-            max-height: 80vh;
-            border: 2px solid #333;
-            border-radius: 8px;
-            background: #000;
-        }
-        .info {
-            margin-top: 20px;
-            padding: 15px;
-            background: #2a2a2a;
-            border-radius: 8px;
-            text-align: center;
-        }
-        .status {
-            color: #4caf50;
-            font-weight: bold;
-        }
-        .error {
-            color: #f44336;
-        }
-    </style>
-</head>
-<body>
-    <h1>WebSocket Screen Stream</h1>
-    <canvas id="stream"></canvas>
-    <div class="info">
-        <div id="status" class="status">Connecting...</div>
-        <p id="fps">FPS: 0 | Latency: 0ms</p>
-    </div>
-    <script>
+fn image_client_script(ws_scheme: &str, remote_input_enabled: bool) -> String {
+    format!(r#"
         const canvas = document.getElementById('stream');
         const ctx = canvas.getContext('2d');
         const statusDiv = document.getElementById('status');
         const fpsDisplay = document.getElementById('fps');
+        const remoteInputEnabled = {remote_input_enabled};
 
         let frameCount = 0;
         let fps = 0;
         let lastUpdate = Date.now();
         let lastFrameTime = Date.now();
+        let lastStatsReport = Date.now();
 
-        const ws = new WebSocket('ws://' + window.location.host);
+        const ws = new WebSocket('{ws_scheme}://' + window.location.host);
         ws.binaryType = 'arraybuffer';
 
-        ws.onopen = function() {
+        ws.onopen = function() {{
             console.log('WebSocket connected');
             statusDiv.textContent = '● Live';
             statusDiv.className = 'status';
-        };
+        }};
 
-        ws.onmessage = function(event) {
+        ws.onmessage = function(event) {{
             const now = Date.now();
             const latency = now - lastFrameTime;
             lastFrameTime = now;
 
-            const blob = new Blob([event.data], { type: 'image/jpeg' });
+            const blob = new Blob([event.data], {{ type: 'image/jpeg' }});
             const url = URL.createObjectURL(blob);
 
             const img = new Image();
-            img.onload = function() {
-                if (canvas.width === 0) {
+            img.onload = function() {{
+                if (canvas.width === 0) {{
                     canvas.width = img.width;
                     canvas.height = img.height;
-                }
+                }}
 
                 ctx.drawImage(img, 0, 0);
                 URL.revokeObjectURL(url);
 
                 frameCount++;
-                if (now - lastUpdate >= 1000) {
+                if (now - lastUpdate >= 1000) {{
                     fps = frameCount;
-                    fpsDisplay.textContent = `FPS: ${fps} | Latency: ${latency}ms`;
+                    fpsDisplay.textContent = `FPS: ${{fps}} | Latency: ${{latency}}ms`;
                     frameCount = 0;
                     lastUpdate = now;
-                }
-            };
+                }}
+
+                // Сообщаем серверу текущую задержку/FPS раз в секунду, чтобы
+                // он мог адаптивно подстроить качество JPEG и частоту кадров.
+                if (now - lastStatsReport >= 1000 && ws.readyState === WebSocket.OPEN) {{
+                    ws.send(JSON.stringify({{ type: 'stats', latency_ms: latency, fps: fps }}));
+                    lastStatsReport = now;
+                }}
+            }};
             img.src = url;
-        };
-
-        ws.onerror = function(error) {
+        }};
+
+        if (remoteInputEnabled) {{
+            canvas.addEventListener('mousemove', (e) => {{
+                const rect = canvas.getBoundingClientRect();
+                const x = Math.round((e.clientX - rect.left) * (canvas.width / rect.width));
+                const y = Math.round((e.clientY - rect.top) * (canvas.height / rect.height));
+                if (ws.readyState === WebSocket.OPEN) {{
+                    ws.send(JSON.stringify({{ type: 'input', kind: 'mouse_move', x: x, y: y }}));
+                }}
+            }});
+            canvas.addEventListener('mousedown', (e) => {{
+                ws.send(JSON.stringify({{ type: 'input', kind: 'mouse_button', button: e.button === 2 ? 'right' : 'left', pressed: true }}));
+            }});
+            canvas.addEventListener('mouseup', (e) => {{
+                ws.send(JSON.stringify({{ type: 'input', kind: 'mouse_button', button: e.button === 2 ? 'right' : 'left', pressed: false }}));
+            }});
+            canvas.addEventListener('wheel', (e) => {{
+                ws.send(JSON.stringify({{ type: 'input', kind: 'scroll', dx: Math.round(e.deltaX), dy: Math.round(e.deltaY) }}));
+            }});
+            window.addEventListener('keydown', (e) => {{
+                ws.send(JSON.stringify({{ type: 'input', kind: 'key', code: e.key, pressed: true }}));
+            }});
+            window.addEventListener('keyup', (e) => {{
+                ws.send(JSON.stringify({{ type: 'input', kind: 'key', code: e.key, pressed: false }}));
+            }});
+        }}
+
+        ws.onerror = function(error) {{
             console.error('WebSocket error:', error);
             statusDiv.textContent = '● Error';
             statusDiv.className = 'error';
-        };
+        }};
 
-        ws.onclose = function() {
+        ws.onclose = function() {{
             console.log('WebSocket disconnected');
             statusDiv.textContent = '● Disconnected';
             statusDiv.className = 'error';
-        };
+        }};
+    "#)
+}
+
+/// Client script for the video-codec path: feeds the raw encoded chunks
+/// straight into a Media Source Extensions `SourceBuffer`.
+fn video_client_script(ws_scheme: &str, mime_type: &str) -> String {
+    format!(r#"
+        const video = document.getElementById('stream');
+        const statusDiv = document.getElementById('status');
+        const fpsDisplay = document.getElementById('fps');
+
+        let chunkCount = 0;
+        let lastUpdate = Date.now();
+
+        const mediaSource = new MediaSource();
+        video.src = URL.createObjectURL(mediaSource);
+
+        mediaSource.addEventListener('sourceopen', () => {{
+            const sourceBuffer = mediaSource.addSourceBuffer('{mime_type}');
+            const queue = [];
+
+            const ws = new WebSocket('{ws_scheme}://' + window.location.host);
+            ws.binaryType = 'arraybuffer';
+
+            ws.onopen = function() {{
+                statusDiv.textContent = '● Live';
+                statusDiv.className = 'status';
+                video.play().catch(() => {{}});
+            }};
+
+            ws.onmessage = function(event) {{
+                queue.push(event.data);
+                if (!sourceBuffer.updating) {{
+                    sourceBuffer.appendBuffer(queue.shift());
+                }}
+
+                chunkCount++;
+                const now = Date.now();
+                if (now - lastUpdate >= 1000) {{
+                    fpsDisplay.textContent = `Chunks/s: ${{chunkCount}}`;
+                    chunkCount = 0;
+                    lastUpdate = now;
+                }}
+            }};
+
+            sourceBuffer.addEventListener('updateend', () => {{
+                if (queue.length > 0 && !sourceBuffer.updating) {{
+                    sourceBuffer.appendBuffer(queue.shift());
+                }}
+            }});
+
+            ws.onerror = function(error) {{
+                console.error('WebSocket error:', error);
+                statusDiv.textContent = '● Error';
+                statusDiv.className = 'error';
+            }};
+
+            ws.onclose = function() {{
+                statusDiv.textContent = '● Disconnected';
+                statusDiv.className = 'error';
+            }};
+        }});
+    "#)
+}
+
+/// Client script for the WebTransport path: frames arrive as unreliable
+/// datagrams (or per-frame unidirectional streams) over QUIC instead of a
+/// single ordered TCP byte stream, so a lost frame never blocks the next one.
+fn webtransport_client_script(wt_port: u16) -> String {
+    format!(r#"
+        const canvas = document.getElementById('stream');
+        const ctx = canvas.getContext('2d');
+        const statusDiv = document.getElementById('status');
+        const fpsDisplay = document.getElementById('fps');
+
+        let frameCount = 0;
+        let lastUpdate = Date.now();
+
+        async function renderFrame(data) {{
+            const blob = new Blob([data], {{ type: 'image/jpeg' }});
+            const bitmap = await createImageBitmap(blob);
+            if (canvas.width === 0) {{
+                canvas.width = bitmap.width;
+                canvas.height = bitmap.height;
+            }}
+            ctx.drawImage(bitmap, 0, 0);
+
+            frameCount++;
+            const now = Date.now();
+            if (now - lastUpdate >= 1000) {{
+                fpsDisplay.textContent = `FPS: ${{frameCount}}`;
+                frameCount = 0;
+                lastUpdate = now;
+            }}
+        }}
+
+        (async () => {{
+            const transport = new WebTransport(`https://${{window.location.hostname}}:{wt_port}/`);
+            await transport.ready;
+            statusDiv.textContent = '● Live (WebTransport)';
+            statusDiv.className = 'status';
+
+            (async () => {{
+                const streams = transport.incomingUnidirectionalStreams.getReader();
+                while (true) {{
+                    const {{ value: stream, done }} = await streams.read();
+                    if (done) break;
+                    new Response(stream).arrayBuffer().then(renderFrame);
+                }}
+            }})();
+
+            const datagrams = transport.datagrams.readable.getReader();
+            while (true) {{
+                const {{ value, done }} = await datagrams.read();
+                if (done) break;
+                renderFrame(value);
+            }}
+        }})().catch((error) => {{
+            console.error('WebTransport error:', error);
+            statusDiv.textContent = '● Error';
+            statusDiv.className = 'error';
+        }});
+    "#)
+}
+
+async fn handle_http<S: AsyncRead + AsyncWrite + Unpin>(mut stream: S, use_tls: bool, codec: StreamCodec, transport: Transport, remote_input_enabled: bool) -> Result<(), String> {
+    let ws_scheme = if use_tls { "wss" } else { "ws" };
+
+    let media_tag = match codec {
+        StreamCodec::Jpeg => "<canvas id=\"stream\"></canvas>".to_string(),
+        StreamCodec::Video(_) => "<video id=\"stream\" autoplay muted></video>".to_string(),
+    };
+
+    let script = match transport {
+        Transport::WebTransport { port: wt_port, .. } => webtransport_client_script(wt_port),
+        Transport::Tcp => match codec {
+            StreamCodec::Jpeg => image_client_script(ws_scheme, remote_input_enabled),
+            StreamCodec::Video(video_codec) => video_client_script(ws_scheme, video_codec.mime_type()),
+        },
+    };
+
+    let html = format!(r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="UTF-8">
+    <title>WebSocket Screen Stream</title>
+    <style>
+        body {{
+            margin: 0;
+            padding: 20px;
+            background: #1a1a1a;
+            color: #fff;
+            font-family: Arial, sans-serif;
+            display: flex;
+            flex-direction: column;
+            align-items: center;
+            justify-content: center;
+            min-height: 100vh;
+        }}
+        h1 {{
+            margin-bottom: 20px;
+        }}
+        canvas, video {{
+            max-width: 90vw;
+            max-height: 80vh;
+            border: 2px solid #333;
+            border-radius: 8px;
+            background: #000;
+        }}
+        .info {{
+            margin-top: 20px;
+            padding: 15px;
+            background: #2a2a2a;
+            border-radius: 8px;
+            text-align: center;
+        }}
+        .status {{
+            color: #4caf50;
+            font-weight: bold;
+        }}
+        .error {{
+            color: #f44336;
+        }}
+    </style>
+</head>
+<body>
+    <h1>WebSocket Screen Stream</h1>
+    {media_tag}
+    <div class="info">
+        <div id="status" class="status">Connecting...</div>
+        <p id="fps">FPS: 0 | Latency: 0ms</p>
+    </div>
+    <script>
+        {script}
     </script>
 </body>
-</html>"#;
+</html>"#);
 
     let response = format!(
         "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
@@ -218,28 +582,64 @@ fn handle_http(mut stream: TcpStream) -> Result<(), String> {
         html
     );
 
-    stream.write_all(response.as_bytes()).map_err(|e| e.to_string())?;
-    stream.flush().map_err(|e| e.to_string())?;
+    stream.write_all(response.as_bytes()).await.map_err(|e| e.to_string())?;
+    stream.flush().await.map_err(|e| e.to_string())?;
     Ok(())
 }
 
-fn handle_websocket(
-    stream: TcpStream,
-    stop_signal: Arc<AtomicBool>,
-    screen_index: usize
-) -> Result<(), String> {
-    let mut websocket = accept(stream)
-        .map_err(|e| format!("WebSocket handshake failed: {}", e))?;
+/// Captures one frame from `screen_index` and encodes it as a JPEG. Blocking;
+/// callers on the async runtime should run this via `spawn_blocking`.
+pub(crate) fn capture_frame_jpeg_sync(screen_index: usize, quality: u8) -> Result<Vec<u8>, String> {
+    let screens = Screen::all()
+        .map_err(|e| format!("Failed to get screens: {}", e))?;
 
-    println!("WebSocket handshake successful, starting stream...");
+    let screen = screens.get(screen_index)
+        .ok_or_else(|| format!("Screen {} not found", screen_index))?;
 
-    let mut frame_count = 0u64;
-    loop {
-        if stop_signal.load(Ordering::SeqCst) {
-            println!("Stop signal received, closing client");
-            break;
-        }
+    let captured = screen.capture()
+        .map_err(|e| format!("Failed to capture: {}", e))?;
+
+    let width = captured.width();
+    let height = captured.height();
+    let rgba_data = captured.rgba();
+
+    let pixel_count = (width * height) as usize;
+    let mut rgb_data = Vec::with_capacity(pixel_count * 3);
+    unsafe {
+        rgb_data.set_len(pixel_count * 3);
+    }
+
+    let mut rgb_idx = 0;
+    for rgba_idx in (0..pixel_count * 4).step_by(4) {
+        rgb_data[rgb_idx] = rgba_data[rgba_idx];
+        rgb_data[rgb_idx + 1] = rgba_data[rgba_idx + 1];
+        rgb_data[rgb_idx + 2] = rgba_data[rgba_idx + 2];
+        rgb_idx += 3;
+    }
+
+    let mut jpeg_data = Vec::new();
+    {
+        let mut cursor = Cursor::new(&mut jpeg_data);
+        let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut cursor, quality);
+        encoder.write_image(&rgb_data, width, height, image::ExtendedColorType::Rgb8)
+            .map_err(|e| format!("Failed to encode JPEG: {}", e))?;
+    }
+
+    Ok(jpeg_data)
+}
+
+/// Async wrapper around `capture_frame_jpeg_sync`, run on the blocking pool
+/// since both capture and encode are CPU-bound.
+async fn capture_frame_jpeg(screen_index: usize, quality: u8) -> Result<Vec<u8>, String> {
+    tokio::task::spawn_blocking(move || capture_frame_jpeg_sync(screen_index, quality))
+        .await
+        .map_err(|e| format!("Capture task panicked: {}", e))?
+}
 
+/// Captures one frame from `screen_index` as raw RGBA, for feeding into the
+/// GStreamer encoder pipeline.
+async fn capture_frame_rgba(screen_index: usize) -> Result<(Vec<u8>, u32, u32), String> {
+    tokio::task::spawn_blocking(move || -> Result<(Vec<u8>, u32, u32), String> {
         let screens = Screen::all()
             .map_err(|e| format!("Failed to get screens: {}", e))?;
 
@@ -249,45 +649,289 @@ fn handle_websocket(
         let captured = screen.capture()
             .map_err(|e| format!("Failed to capture: {}", e))?;
 
-        let width = captured.width();
-        let height = captured.height();
-        let rgba_data = captured.rgba();
+        Ok((captured.rgba().to_vec(), captured.width(), captured.height()))
+    })
+    .await
+    .map_err(|e| format!("Capture task panicked: {}", e))?
+}
+
+/// How long we wait for a client pong before considering it dead.
+const PONG_TIMEOUT_MULTIPLIER: u32 = 2;
+
+/// Messages the browser client sends back over the same WebSocket: latency
+/// reports used to drive adaptive quality, and (when enabled) input events
+/// for remote control.
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    Stats { latency_ms: f64, fps: f64 },
+    Input(InputEvent),
+}
 
-        let pixel_count = (width * height) as usize;
-        let mut rgb_data = Vec::with_capacity(pixel_count * 3);
-        unsafe {
-            rgb_data.set_len(pixel_count * 3);
+#[derive(Deserialize, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum InputEvent {
+    MouseMove { x: i32, y: i32 },
+    MouseButton { button: String, pressed: bool },
+    Key { code: String, pressed: bool },
+    Scroll { dx: i32, dy: i32 },
+}
+
+/// Per-client tunables the adaptive-quality back-channel adjusts in place.
+struct ClientTuning {
+    jpeg_quality: u8,
+    frame_interval: Duration,
+}
+
+impl ClientTuning {
+    fn new() -> Self {
+        Self {
+            jpeg_quality: DEFAULT_JPEG_QUALITY,
+            frame_interval: Duration::from_millis(50),
         }
+    }
 
-        let mut rgb_idx = 0;
-        for rgba_idx in (0..pixel_count * 4).step_by(4) {
-            rgb_data[rgb_idx] = rgba_data[rgba_idx];
-            rgb_data[rgb_idx + 1] = rgba_data[rgba_idx + 1];
-            rgb_data[rgb_idx + 2] = rgba_data[rgba_idx + 2];
-            rgb_idx += 3;
+    /// Backs off quality/frame rate when the client reports rising latency
+    /// or a falling FPS, and relaxes back up when the client is keeping up.
+    fn apply_stats(&mut self, latency_ms: f64, fps: f64) {
+        const MIN_QUALITY: u8 = 20;
+        const MAX_QUALITY: u8 = 80;
+        const MIN_INTERVAL_MS: u64 = 33; // ~30 FPS cap
+        const MAX_INTERVAL_MS: u64 = 200; // ~5 FPS floor
+
+        let falling_behind = latency_ms > 150.0 || fps < 15.0;
+
+        if falling_behind {
+            self.jpeg_quality = self.jpeg_quality.saturating_sub(5).max(MIN_QUALITY);
+            let interval_ms = (self.frame_interval.as_millis() as u64 + 10).min(MAX_INTERVAL_MS);
+            self.frame_interval = Duration::from_millis(interval_ms);
+        } else {
+            self.jpeg_quality = (self.jpeg_quality + 5).min(MAX_QUALITY);
+            let interval_ms = self.frame_interval.as_millis().saturating_sub(10).max(MIN_INTERVAL_MS as u128) as u64;
+            self.frame_interval = Duration::from_millis(interval_ms);
         }
+    }
+}
 
-        let mut jpeg_data = Vec::new();
-        {
-            let mut cursor = Cursor::new(&mut jpeg_data);
-            let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut cursor, 60);
-            if let Err(e) = encoder.write_image(&rgb_data, width, height, image::ExtendedColorType::Rgb8) {
-                eprintln!("Failed to encode JPEG: {}", e);
-                continue;
+/// Maps a browser `KeyboardEvent.key` string to the `enigo::Key` it
+/// represents. Mirrors `input_backend::rdev_key_to_enigo`'s approach, but the
+/// names differ: the browser already spells out most non-printable keys
+/// (`"Enter"`, `"ArrowUp"`, ...) instead of rdev's `Debug`-format names
+/// (`"Return"`, `"UpArrow"`, ...). Anything not listed here falls back to the
+/// first character, which is correct for ordinary printable keys.
+fn js_key_to_enigo(code: &str) -> enigo::Key {
+    use enigo::Key;
+
+    match code {
+        "Enter" => Key::Return,
+        "Escape" => Key::Escape,
+        "Backspace" => Key::Backspace,
+        "Tab" => Key::Tab,
+        " " | "Spacebar" => Key::Space,
+        "Delete" => Key::Delete,
+        "Home" => Key::Home,
+        "End" => Key::End,
+        "ArrowUp" => Key::UpArrow,
+        "ArrowDown" => Key::DownArrow,
+        "ArrowLeft" => Key::LeftArrow,
+        "ArrowRight" => Key::RightArrow,
+        "Shift" => Key::Shift,
+        "Control" => Key::Control,
+        "Alt" | "AltGraph" => Key::Alt,
+        "Meta" => Key::Meta,
+        _ => Key::Unicode(code.chars().next().unwrap_or(' ')),
+    }
+}
+
+/// Drives an `enigo` input backend from a parsed client input event. Runs on
+/// the blocking pool since `enigo` is a synchronous API.
+async fn apply_input_event(event: InputEvent) {
+    tokio::task::spawn_blocking(move || {
+        use enigo::{Axis, Button, Coordinate, Direction, Enigo, Keyboard, Mouse, Settings};
+
+        let mut enigo = match Enigo::new(&Settings::default()) {
+            Ok(enigo) => enigo,
+            Err(e) => {
+                eprintln!("Failed to create Enigo for remote input: {:?}", e);
+                return;
             }
+        };
+
+        let result = match event {
+            InputEvent::MouseMove { x, y } => enigo.move_mouse(x, y, Coordinate::Abs).map_err(|e| format!("{:?}", e)),
+            InputEvent::MouseButton { button, pressed } => {
+                let btn = if button == "right" { Button::Right } else { Button::Left };
+                let direction = if pressed { Direction::Press } else { Direction::Release };
+                enigo.button(btn, direction).map_err(|e| format!("{:?}", e))
+            }
+            InputEvent::Key { code, pressed } => {
+                let direction = if pressed { Direction::Press } else { Direction::Release };
+                enigo.key(js_key_to_enigo(&code), direction).map_err(|e| format!("{:?}", e))
+            }
+            InputEvent::Scroll { dx, dy } => enigo
+                .scroll(dy, Axis::Vertical)
+                .and_then(|_| enigo.scroll(dx, Axis::Horizontal))
+                .map_err(|e| format!("{:?}", e)),
+        };
+
+        if let Err(e) = result {
+            eprintln!("Failed to apply remote input event: {}", e);
         }
+    })
+    .await
+    .ok();
+}
+
+async fn handle_websocket<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: S,
+    shutdown: Arc<Notify>,
+    screen_index: usize,
+    keepalive_interval: Duration,
+    codec: StreamCodec,
+    remote_input_enabled: bool,
+) -> Result<(), String> {
+    use futures_util::{SinkExt, StreamExt};
+    use tungstenite::protocol::{frame::coding::CloseCode, CloseFrame};
+
+    let mut websocket = accept_async(stream)
+        .await
+        .map_err(|e| format!("WebSocket handshake failed: {}", e))?;
+
+    println!("WebSocket handshake successful, starting stream...");
+
+    let mut tuning = ClientTuning::new();
+    let mut frame_ticker = tokio::time::interval(tuning.frame_interval);
+    let mut keepalive_ticker = tokio::time::interval(keepalive_interval);
+    let pong_timeout = keepalive_interval * PONG_TIMEOUT_MULTIPLIER;
+    let mut last_pong = tokio::time::Instant::now();
+    let mut frame_count = 0u64;
+    let mut gst_encoder: Option<GstEncoder> = None;
 
-        if let Err(e) = websocket.send(Message::Binary(jpeg_data)) {
-            eprintln!("Failed to send frame: {}", e);
+    loop {
+        if last_pong.elapsed() > pong_timeout {
+            println!("No pong received within {:?}, dropping unresponsive client", pong_timeout);
             break;
         }
 
-        frame_count += 1;
-        if frame_count % 30 == 0 {
-            println!("Sent {} frames", frame_count);
-        }
+        tokio::select! {
+            _ = shutdown.notified() => {
+                println!("Stop signal received, closing client");
+                let _ = websocket.send(Message::Close(Some(CloseFrame {
+                    code: CloseCode::Away,
+                    reason: "server shutting down".into(),
+                }))).await;
+                break;
+            }
+            _ = keepalive_ticker.tick() => {
+                if let Err(e) = websocket.send(Message::Ping(Vec::new())).await {
+                    eprintln!("Failed to send keepalive ping: {}", e);
+                    break;
+                }
+            }
+            incoming = websocket.next() => {
+                match incoming {
+                    Some(Ok(Message::Ping(payload))) => {
+                        if let Err(e) = websocket.send(Message::Pong(payload)).await {
+                            eprintln!("Failed to send pong: {}", e);
+                            break;
+                        }
+                    }
+                    Some(Ok(Message::Pong(_))) => {
+                        last_pong = tokio::time::Instant::now();
+                    }
+                    Some(Ok(Message::Close(frame))) => {
+                        println!("Client requested close: {:?}", frame);
+                        let _ = websocket.send(Message::Close(Some(CloseFrame {
+                            code: CloseCode::Normal,
+                            reason: "bye".into(),
+                        }))).await;
+                        break;
+                    }
+                    Some(Ok(Message::Text(text))) => {
+                        match serde_json::from_str::<ClientMessage>(&text) {
+                            Ok(ClientMessage::Stats { latency_ms, fps }) => {
+                                tuning.apply_stats(latency_ms, fps);
+                                frame_ticker = tokio::time::interval(tuning.frame_interval);
+                            }
+                            Ok(ClientMessage::Input(event)) => {
+                                if remote_input_enabled {
+                                    apply_input_event(event).await;
+                                }
+                            }
+                            Err(e) => {
+                                eprintln!("Failed to parse client control message: {}", e);
+                            }
+                        }
+                    }
+                    Some(Ok(_)) => {
+                        // Остальные типы сообщений от клиента пока игнорируются
+                    }
+                    Some(Err(e)) => {
+                        eprintln!("WebSocket read error: {}", e);
+                        break;
+                    }
+                    None => {
+                        println!("Client closed the connection");
+                        break;
+                    }
+                }
+            }
+            _ = frame_ticker.tick() => {
+                match codec {
+                    StreamCodec::Jpeg => {
+                        let jpeg_data = match capture_frame_jpeg(screen_index, tuning.jpeg_quality).await {
+                            Ok(data) => data,
+                            Err(e) => {
+                                eprintln!("{}", e);
+                                continue;
+                            }
+                        };
+
+                        if let Err(e) = websocket.send(Message::Binary(jpeg_data)).await {
+                            eprintln!("Failed to send frame: {}", e);
+                            break;
+                        }
+                    }
+                    StreamCodec::Video(video_codec) => {
+                        let (rgba, width, height) = match capture_frame_rgba(screen_index).await {
+                            Ok(frame) => frame,
+                            Err(e) => {
+                                eprintln!("{}", e);
+                                continue;
+                            }
+                        };
+
+                        if gst_encoder.is_none() {
+                            match GstEncoder::new(video_codec, width, height, 20) {
+                                Ok(encoder) => gst_encoder = Some(encoder),
+                                Err(e) => {
+                                    eprintln!("Failed to start video pipeline: {}", e);
+                                    break;
+                                }
+                            }
+                        }
 
-        thread::sleep(Duration::from_millis(50));
+                        let encoder = gst_encoder.as_mut().expect("encoder initialized above");
+                        if let Err(e) = encoder.push_frame(&rgba) {
+                            eprintln!("Failed to push frame into video pipeline: {}", e);
+                            continue;
+                        }
+
+                        while let Some(chunk) = encoder.try_pull_encoded() {
+                            if let Err(e) = websocket.send(Message::Binary(chunk)).await {
+                                eprintln!("Failed to send encoded chunk: {}", e);
+                                break;
+                            }
+                        }
+                    }
+                }
+
+                frame_count += 1;
+                if frame_count % 30 == 0 {
+                    println!("Sent {} frames", frame_count);
+                }
+            }
+        }
     }
 
     println!("Client disconnected, sent {} frames total", frame_count);
@@ -0,0 +1,259 @@
+//! QUIC/HTTP3 WebTransport transport, offered as an alternative to the plain
+//! TCP WebSocket path in `websocket_stream`. A single dropped packet on a
+//! TCP connection stalls every frame behind it; WebTransport lets us send
+//! each frame as its own unreliable datagram (or its own unidirectional
+//! stream) so a lost frame never blocks the next one.
+//!
+//! Built on neqo's `Http3Server` / WebTransport extension, following the
+//! session/event model neqo exposes for WebTransport servers.
+//!
+//! neqo's TLS stack (neqo_crypto) is NSS-backed and only terminates TLS with
+//! a certificate that's already sitting in an NSS database under a known
+//! nickname, so the configured `TlsConfig` has to be imported there before
+//! the server can start; see `import_cert_into_nss_db` below.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use neqo_http3::{Http3Server, Http3Parameters, Http3ServerEvent, Output};
+use neqo_transport::{ConnectionParameters, StreamId};
+use neqo_common::Datagram;
+use tokio::net::UdpSocket;
+use tokio::process::Command;
+use tokio::sync::Notify;
+
+/// Largest UDP datagram we'll read off the socket in one go; comfortably
+/// above the largest QUIC packet any path MTU in practice allows.
+const MAX_DATAGRAM_SIZE: usize = 65_527;
+
+use crate::websocket_stream::TlsConfig;
+
+/// How a single encoded frame is delivered over the WebTransport session.
+#[derive(Clone, Copy, Debug)]
+pub enum FrameDelivery {
+    /// Send as an unreliable QUIC datagram; stale frames are simply dropped
+    /// instead of queuing behind a lost one.
+    Datagram,
+    /// Send on a fresh unidirectional stream per frame, so a lost frame
+    /// can be retransmitted without blocking later frames.
+    UnidirectionalStream,
+}
+
+pub struct WebTransportConfig {
+    pub port: u16,
+    pub tls: TlsConfig,
+    pub delivery: FrameDelivery,
+}
+
+/// Accepts WebTransport sessions over HTTP/3 and streams encoded frames to
+/// each session using `delivery`. Mirrors `websocket_stream::start_websocket_server`'s
+/// shutdown/frame-producer shape so the two transports are interchangeable.
+pub async fn start_webtransport_server(
+    config: WebTransportConfig,
+    shutdown: Arc<Notify>,
+    mut next_frame: impl FnMut() -> Option<Vec<u8>> + Send + 'static,
+) -> Result<tokio::task::JoinHandle<()>, String> {
+    let addr: SocketAddr = format!("0.0.0.0:{}", config.port)
+        .parse()
+        .map_err(|e| format!("Invalid bind address: {}", e))?;
+
+    println!("Starting WebTransport/HTTP3 server on {} ({:?} delivery)", addr, config.delivery);
+
+    let socket = UdpSocket::bind(addr)
+        .await
+        .map_err(|e| format!("Failed to bind UDP socket: {}", e))?;
+
+    let cert_nickname = import_cert_into_nss_db(&config.tls).await?;
+
+    let mut http3_params = Http3Parameters::default();
+    http3_params = http3_params.webtransport(true);
+
+    let mut server = Http3Server::new(
+        neqo_common::qlog::NeqoQlog::disabled(),
+        &[&cert_nickname],
+        ConnectionParameters::default().max_streams_bidi(256).max_streams_uni(256),
+        http3_params,
+        addr,
+    )
+    .map_err(|e| format!("Failed to create Http3Server: {}", e))?;
+
+    let handle = tokio::spawn(async move {
+        let mut delivery_ticker = tokio::time::interval(Duration::from_millis(20));
+        let mut recv_buf = vec![0u8; MAX_DATAGRAM_SIZE];
+
+        loop {
+            tokio::select! {
+                _ = shutdown.notified() => {
+                    println!("Stop signal received, shutting down WebTransport server");
+                    break;
+                }
+                received = socket.recv_from(&mut recv_buf) => {
+                    match received {
+                        Ok((len, peer)) => {
+                            let input = Datagram::new(peer, addr, recv_buf[..len].to_vec());
+                            drive_server(&mut server, &socket, Some(input)).await;
+                        }
+                        Err(e) => {
+                            eprintln!("WebTransport UDP recv error: {}", e);
+                        }
+                    }
+                }
+                _ = delivery_ticker.tick() => {
+                    for event in server.events() {
+                        match event {
+                            Http3ServerEvent::WebTransportSessionRequest { session, .. } => {
+                                println!("Accepting WebTransport session {:?}", session);
+                                server.webtransport_session_accept(session);
+                            }
+                            Http3ServerEvent::WebTransportSessionClosed { session, .. } => {
+                                println!("WebTransport session {:?} closed", session);
+                            }
+                            Http3ServerEvent::WebTransportDatagram { session, .. } => {
+                                // Incoming datagrams are discarded; this server-side
+                                // loop only pushes outgoing frames.
+                                let _ = session;
+                            }
+                            _ => {}
+                        }
+                    }
+
+                    if let Some(frame) = next_frame() {
+                        match config.delivery {
+                            FrameDelivery::Datagram => {
+                                for session in server.webtransport_sessions() {
+                                    let datagram = Datagram::new(addr, addr, frame.clone());
+                                    let _ = server.send_datagram(session, datagram);
+                                }
+                            }
+                            FrameDelivery::UnidirectionalStream => {
+                                for session in server.webtransport_sessions() {
+                                    if let Ok(stream_id) = server.webtransport_create_stream(session, StreamId::new(0)) {
+                                        let _ = server.stream_send(stream_id, &frame);
+                                        let _ = server.stream_close_send(stream_id);
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    // Прогоняем сервер без нового входящего пакета, чтобы вытолкнуть
+                    // исходящие датаграммы, поставленные в очередь выше, на сокет.
+                    drive_server(&mut server, &socket, None).await;
+                }
+            }
+        }
+
+        println!("WebTransport server stopped");
+    });
+
+    Ok(handle)
+}
+
+/// Прогоняет один шаг neqo через `Http3Server::process`, подавая входящий
+/// пакет (если есть) и вычитывая все исходящие датаграммы на UDP-сокет, пока
+/// сервер не перестанет что-либо возвращать.
+async fn drive_server(server: &mut Http3Server, socket: &UdpSocket, input: Option<Datagram>) {
+    let mut next_input = input;
+
+    loop {
+        match server.process(next_input.take(), Instant::now()) {
+            Output::Datagram(out) => {
+                if let Err(e) = socket.send_to(&out, out.destination()).await {
+                    eprintln!("WebTransport UDP send error: {}", e);
+                }
+            }
+            Output::Callback(_) | Output::None => break,
+        }
+    }
+}
+
+/// neqo's `Http3Server` doesn't take a cert/key path or raw DER bytes — it
+/// looks up a certificate by *nickname* in neqo_crypto's NSS database. So
+/// unlike `build_tls_acceptor` in `websocket_stream` (which hands rustls the
+/// cert/key bytes directly), serving TLS here means actually importing the
+/// configured material into that NSS database first and handing back the
+/// nickname we imported it under.
+///
+/// We bundle cert+key into a throwaway PKCS#12 file with `openssl` and import
+/// that into the DB with `pk12util`, since NSS only accepts cert+key pairs as
+/// a matched identity that way, not as a bare PEM cert.
+async fn import_cert_into_nss_db(tls: &TlsConfig) -> Result<String, String> {
+    let db_dir = directories::ProjectDirs::from("com", "bro", "bro")
+        .map(|dirs| dirs.config_dir().join("nss-db"))
+        .ok_or("Failed to resolve config directory for NSS database")?;
+    tokio::fs::create_dir_all(&db_dir)
+        .await
+        .map_err(|e| format!("Failed to create NSS database dir: {}", e))?;
+    neqo_crypto::init_db(&db_dir).map_err(|e| format!("Failed to initialize NSS database: {:?}", e))?;
+
+    let nickname = format!("bro-webtransport-{}", std::process::id());
+    let workdir = std::env::temp_dir().join(format!("bro-webtransport-cert-{}", std::process::id()));
+    tokio::fs::create_dir_all(&workdir)
+        .await
+        .map_err(|e| format!("Failed to create cert staging dir: {}", e))?;
+
+    let (cert_path, key_path) = match tls {
+        TlsConfig::Files { cert_path, key_path } => (cert_path.clone(), key_path.clone()),
+        TlsConfig::Der { cert_chain, private_key } => {
+            let cert_pem = workdir.join("cert.pem");
+            let key_pem = workdir.join("key.pem");
+            write_der_as_pem(&cert_pem, "CERTIFICATE", cert_chain.first().ok_or("Empty DER cert chain")?).await?;
+            write_der_as_pem(&key_pem, "PRIVATE KEY", private_key).await?;
+            (cert_pem.to_string_lossy().to_string(), key_pem.to_string_lossy().to_string())
+        }
+    };
+
+    let p12_path = workdir.join("bundle.p12");
+    let export = Command::new("openssl")
+        .args(&[
+            "pkcs12", "-export",
+            "-in", &cert_path,
+            "-inkey", &key_path,
+            "-out", &p12_path.to_string_lossy(),
+            "-name", &nickname,
+            "-passout", "pass:",
+        ])
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run openssl: {}", e))?;
+
+    if !export.status.success() {
+        let stderr = String::from_utf8_lossy(&export.stderr);
+        return Err(format!("Failed to bundle cert/key into PKCS#12: {}", stderr));
+    }
+
+    let import = Command::new("pk12util")
+        .args(&[
+            "-d", &format!("sql:{}", db_dir.to_string_lossy()),
+            "-i", &p12_path.to_string_lossy(),
+            "-W", "",
+        ])
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run pk12util: {}", e))?;
+
+    let _ = tokio::fs::remove_dir_all(&workdir).await;
+
+    if !import.status.success() {
+        let stderr = String::from_utf8_lossy(&import.stderr);
+        return Err(format!("Failed to import cert into NSS database: {}", stderr));
+    }
+
+    Ok(nickname)
+}
+
+/// Writes raw DER bytes out as a PEM file, since both `openssl pkcs12` and
+/// neqo's own tooling expect PEM on disk rather than bare DER.
+async fn write_der_as_pem(path: &std::path::Path, label: &str, der: &[u8]) -> Result<(), String> {
+    let encoded = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, der);
+    let mut pem = format!("-----BEGIN {}-----\n", label);
+    for chunk in encoded.as_bytes().chunks(64) {
+        pem.push_str(std::str::from_utf8(chunk).unwrap());
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {}-----\n", label));
+
+    tokio::fs::write(path, pem)
+        .await
+        .map_err(|e| format!("Failed to write PEM file {}: {}", path.display(), e))
+}